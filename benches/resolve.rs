@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use deck::unstable::{
+    check, evaluate, parse_signature, EvalDefValue, EvalIdents, EvalIdentsExtensions, EvalStack,
+    Interner, SemNode, Span,
+};
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+const EVAL_SIZES: [usize; 2] = [100, 1_000];
+
+/// Create a stack with `n` functions and `n` operators.
+fn stack(n: usize) -> EvalStack {
+    let mut stack = EvalStack::new();
+    for i in 0..n {
+        for key in [format!("f{i} $x"), format!("$a op{i} $b")] {
+            let key = parse_signature(&key, stack.interner_mut());
+            stack
+                .push_def(key, EvalDefValue::Base, Span::of_src(""))
+                .unwrap();
+        }
+    }
+    stack
+}
+
+/// Resolve by trying every definition, as done before definitions were indexed.
+fn resolve_linear<'s>(stack: &'s EvalStack, ident: &EvalIdents) -> Option<&'s EvalIdents> {
+    let scopes = stack.scopes().collect::<Vec<_>>();
+    scopes
+        .into_iter()
+        .rev()
+        .flat_map(|scope| scope.iter().rev())
+        .find(|def| matches!(def.key.matches(ident), Ok(Some(_))))
+        .map(|def| &def.key)
+}
+
+/// Generate a program of `n` definitions, each referring to the previous one.
+fn program(n: usize) -> String {
+    let mut src = String::from("c0 {}\n");
+    for i in 1..n {
+        src.push_str(&format!("c{i} {{}}\nc{i} {{ c{} }}\n", i - 1));
+    }
+    src
+}
+
+fn bench_resolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    for n in SIZES {
+        let mut stack = stack(n);
+        // The earliest definitions are the last ones reached by a linear scan
+        let idents = [
+            parse_signature("f0 1", stack.interner_mut()),
+            parse_signature("1 op0 2", stack.interner_mut()),
+        ];
+
+        group.bench_with_input(BenchmarkId::new("indexed", n), &idents, |b, idents| {
+            b.iter(|| {
+                for ident in idents {
+                    black_box(stack.resolve(ident).unwrap());
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("linear", n), &idents, |b, idents| {
+            b.iter(|| {
+                for ident in idents {
+                    black_box(resolve_linear(&stack, ident));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_eval(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eval");
+    group.sample_size(10);
+    for n in EVAL_SIZES {
+        let mut interner = Interner::new();
+        let nodes: Arc<[SemNode]> = check(&program(n), &mut interner).nodes.into();
+        group.bench_with_input(BenchmarkId::from_parameter(n), &nodes, |b, nodes| {
+            b.iter(|| evaluate(nodes.clone(), interner.clone()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_resolve, bench_eval);
+criterion_main!(benches);
@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+
+use super::*;
+use crate::{
+    CheckResult, Diagnostic, Diagnostics, EvalError, EvalIdents, EvalIdentsError,
+    EvalIdentsExtensions, EvalIdentsKind, EvalNatives, Interner, SemNode, SemNodeExpr,
+    SemNodeExprKind, SemNodeKind, SimpleDisplay, Span, SrcId, Symbol, EVAL_DBG,
+};
+
+/// Analyze semantic nodes without evaluating them, with the interner the
+/// symbols of `nodes` come from.
+pub fn analyze(nodes: &[SemNode], interner: &mut Interner) -> Diagnostics {
+    Analyzer::new(interner).analyze(nodes)
+}
+
+/// Body of a definition, analyzed after its enclosing scope.
+struct AnalyzerBody<'a> {
+    params: Vec<(Symbol, Span)>,
+    body: &'a [SemNode],
+    exprs: &'a [SemNodeExpr],
+    span: Span,
+}
+
+/// Static analyzer.
+///
+/// Resolves names the same way as [`crate::Evaluator`], and reports errors
+/// the evaluator would run into:
+/// - expressions that never resolve against any visible definition
+/// - definitions whose signature contains only parameters
+/// - bodies without return expressions
+///
+/// It also reports [`Lint`] warnings, unless a directive of the file allows them.
+///
+/// Bodies are evaluated when their definitions are called, and can use every
+/// definition of the enclosing scopes, so they are analyzed after their
+/// enclosing scope.
+///
+/// Files analyzed with [`Analyzer::analyze_file`] share the root scope, like
+/// sources loaded into one [`crate::Interpreter`].
+#[derive(Debug)]
+pub struct Analyzer<'i> {
+    stack: AnalyzerStack,
+
+    /// Lints allowed in each source.
+    allows: HashMap<SrcId, LintAllows>,
+    diagnostics: Diagnostics,
+    interner: &'i Interner,
+}
+
+impl<'i> Analyzer<'i> {
+    /// Create a new analyzer with the builtin native functions, interned
+    /// into `interner`.
+    pub fn new(interner: &'i mut Interner) -> Self {
+        let natives = EvalNatives::with_builtins(interner);
+        Self::with_natives(&natives, interner)
+    }
+
+    /// Create a new analyzer with native functions, whose symbols and the
+    /// ones of the analyzed nodes resolve through `interner`.
+    pub fn with_natives(natives: &EvalNatives, interner: &'i Interner) -> Self {
+        Self {
+            stack: AnalyzerStack::with_natives(natives),
+            allows: HashMap::new(),
+            diagnostics: Diagnostics::default(),
+            interner,
+        }
+    }
+
+    /// Analyze the nodes of a file.
+    ///
+    /// Only directives written as definitions are read, use
+    /// [`Analyzer::analyze_checked`] to read the ones in comments.
+    pub fn analyze(mut self, nodes: &[SemNode]) -> Diagnostics {
+        let id = nodes
+            .first()
+            .map_or_else(SrcId::default, |node| node.span.src);
+        let allows = LintAllows::from_nodes(nodes, self.interner);
+        self.analyze_with_allows(id, nodes, allows);
+        self.finish()
+    }
+
+    /// Analyze the nodes of a checked file, with the directives in its comments.
+    pub fn analyze_checked(mut self, result: &CheckResult) -> Diagnostics {
+        self.analyze_file(result);
+        self.finish()
+    }
+
+    /// Analyze the nodes of a checked file after the files analyzed before,
+    /// whose definitions it can use.
+    ///
+    /// Unused definitions of the root scope are reported by [`Analyzer::finish`],
+    /// once every file had a chance to use them.
+    pub fn analyze_file(&mut self, result: &CheckResult) {
+        let mut allows = LintAllows::from_comments(&result.comments);
+        allows.extend(LintAllows::from_nodes(&result.nodes, self.interner));
+        self.analyze_with_allows(result.id, &result.nodes, allows);
+    }
+
+    /// Report the unused definitions of the root scope, and get every diagnostic
+    /// sorted by source and position.
+    pub fn finish(mut self) -> Diagnostics {
+        if let Some(scope) = self.stack.pop_scope() {
+            self.report_unused(scope);
+        }
+
+        self.diagnostics.sort();
+        self.diagnostics
+    }
+
+    /// Analyze the nodes of a file with the lints allowed in it.
+    fn analyze_with_allows(&mut self, id: SrcId, nodes: &[SemNode], mut allows: LintAllows) {
+        for (name, span) in std::mem::take(&mut allows.unknown) {
+            self.diagnostics.push(
+                Diagnostic::warning(format!("unknown lint: {name}"), span)
+                    .with_label("not a lint name"),
+            );
+        }
+        self.allows.entry(id).or_default().extend(allows);
+
+        self.analyze_scope(nodes);
+    }
+
+    /// Analyze the nodes of a scope, then the bodies defined in it.
+    fn analyze_scope(&mut self, nodes: &[SemNode]) {
+        let mut bodies = vec![];
+
+        for node in nodes {
+            if let Some(body) = self.analyze_def(node) {
+                bodies.push(body);
+            }
+        }
+
+        for AnalyzerBody {
+            params,
+            body,
+            exprs,
+            span,
+        } in bodies
+        {
+            self.stack.push_scope();
+            for (param, param_span) in params {
+                self.stack.push_def(
+                    vec![EvalIdentsKind::word(param, self.interner)],
+                    AnalyzerDefKind::Param,
+                    param_span,
+                );
+            }
+            self.analyze_scope(body);
+            self.check_exprs(exprs, &span);
+            if let Some(scope) = self.stack.pop_scope() {
+                self.report_unused(scope);
+            }
+        }
+    }
+
+    /// Analyze a definition node.
+    ///
+    /// Returns the body of a definition with a body, to be analyzed after the scope.
+    fn analyze_def<'a>(&mut self, node: &'a SemNode) -> Option<AnalyzerBody<'a>> {
+        let SemNodeKind::Def {
+            idents,
+            body,
+            exprs,
+        } = &node.value
+        else {
+            return None;
+        };
+
+        if idents.is_empty() {
+            return None;
+        }
+
+        // `dbg! { exprs }` calls the native `dbg! (exprs)`
+        let dbg = matches!(
+            idents.last(),
+            Some(SemNodeExpr { value: SemNodeExprKind::Ident(y), .. }) if self.interner.resolve(*y) == EVAL_DBG,
+        );
+
+        if !body.is_empty() {
+            if exprs.is_empty() {
+                self.error(EvalError::BodyWithoutExprs {
+                    span: node.span.clone(),
+                });
+                return None;
+            }
+
+            let key = self.eval_idents(idents, true);
+            let params = params(&key, idents);
+            self.push_def(key, AnalyzerDefKind::Node, idents, &node.span)
+                .then_some(AnalyzerBody {
+                    params,
+                    body,
+                    exprs,
+                    span: node.span.clone(),
+                })
+        } else {
+            let kind = match exprs.is_empty() {
+                true => AnalyzerDefKind::Base,
+                false => {
+                    self.check_exprs(exprs, &node.span);
+                    AnalyzerDefKind::Ref
+                }
+            };
+
+            if !dbg {
+                let resolve = kind != AnalyzerDefKind::Base || idents.len() != 1;
+                let key = self.eval_idents(idents, resolve);
+                self.push_def(key, kind, idents, &node.span);
+            }
+
+            None
+        }
+    }
+
+    /// Push a definition if its signature is valid.
+    fn push_def(
+        &mut self,
+        key: EvalIdents,
+        kind: AnalyzerDefKind,
+        idents: &[SemNodeExpr],
+        span: &Span,
+    ) -> bool {
+        let span = exprs_span(idents).unwrap_or_else(|| span.clone());
+
+        if key.iter().all(|x| matches!(x, EvalIdentsKind::Param(_))) {
+            self.error(EvalError::ParamOnlySignature { span });
+            return false;
+        }
+
+        if let Some(param) = key.duplicate_param() {
+            self.error(EvalError::Idents {
+                error: EvalIdentsError::DuplicateParam(param),
+                span,
+            });
+            return false;
+        }
+
+        for def in self.stack.shadow(&key) {
+            self.warn(
+                Lint::ShadowedSignature,
+                Diagnostic::warning(
+                    format!(
+                        "shadowed signature: {}",
+                        def.key.simple_display(self.interner)
+                    ),
+                    def.span,
+                )
+                .with_label("shadowed before it is used")
+                .with_secondary(span.clone(), "shadowed by this definition"),
+            );
+        }
+
+        self.stack.push_def(key, kind, span);
+        true
+    }
+
+    /// Check that expressions resolve, reporting an error otherwise.
+    fn check_exprs(&mut self, exprs: &[SemNodeExpr], span: &Span) {
+        let idents = self.eval_idents(exprs, false);
+        if !self.resolves(&idents) {
+            self.error(EvalError::IdentsNotFound {
+                idents,
+                span: exprs_span(exprs).unwrap_or_else(|| span.clone()),
+            });
+        }
+    }
+
+    /// Check if identifiers resolve, including the arguments of bases and of
+    /// natives that evaluate them.
+    ///
+    /// Arguments of other definitions are resolved lazily by the evaluator,
+    /// so they only mark the definitions they use.
+    fn resolves(&mut self, idents: &EvalIdents) -> bool {
+        let Some((kind, args)) = self.stack.resolve(idents) else {
+            return false;
+        };
+
+        let mut resolves = true;
+        for arg in args.into_values() {
+            resolves &= match arg {
+                EvalIdentsKind::Inner(inner) => self.resolves(&inner),
+                arg => self.resolves(&vec![arg]),
+            };
+        }
+
+        resolves || !kind.resolves_args()
+    }
+
+    /// Convert expressions to identifiers.
+    ///
+    /// If `resolve` is true, identifiers that are not defined are parameters.
+    /// A signature with a single identifier redefines it rather than using it.
+    fn eval_idents(&mut self, exprs: &[SemNodeExpr], resolve: bool) -> EvalIdents {
+        let redefines = exprs.len() == 1;
+        exprs
+            .iter()
+            .filter_map(|expr| match &expr.value {
+                SemNodeExprKind::Ident(ident) if resolve => {
+                    let word = EvalIdentsKind::word(*ident, self.interner);
+                    let idents = vec![word.clone()];
+                    let defined = match redefines {
+                        true => self.stack.contains(&idents),
+                        false => self.stack.resolve(&idents).is_some(),
+                    };
+                    Some(match defined {
+                        true => word,
+                        false => EvalIdentsKind::Param(*ident),
+                    })
+                }
+                SemNodeExprKind::Ident(ident) => Some(EvalIdentsKind::word(*ident, self.interner)),
+                SemNodeExprKind::Str(value) => Some(EvalIdentsKind::Str(value.clone())),
+                SemNodeExprKind::Inner(inner) => {
+                    Some(EvalIdentsKind::Inner(self.eval_idents(inner, resolve)))
+                }
+                SemNodeExprKind::Error { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Report unused definitions and parameters of a popped scope.
+    fn report_unused(&mut self, scope: Vec<AnalyzerDef>) {
+        for def in scope.into_iter().filter(|def| !def.used) {
+            let name = def.key.simple_display(self.interner);
+            let (lint, diagnostic) = match def.kind {
+                AnalyzerDefKind::Param => (
+                    Lint::UnusedParameter,
+                    Diagnostic::warning(format!("unused parameter: {name}"), def.span)
+                        .with_label("never used in the body"),
+                ),
+                _ => (
+                    Lint::UnusedDefinition,
+                    Diagnostic::warning(format!("unused definition: {name}"), def.span)
+                        .with_label("never used"),
+                ),
+            };
+            self.warn(lint, diagnostic);
+        }
+    }
+
+    /// Report an error.
+    fn error(&mut self, error: EvalError) {
+        self.diagnostics.push(error.diagnostic(self.interner));
+    }
+
+    /// Report a lint warning, unless the source it points into allows it.
+    fn warn(&mut self, lint: Lint, diagnostic: Diagnostic) {
+        let allows = self.allows.get(&diagnostic.primary.span.src);
+        if !allows.is_some_and(|allows| allows.is_allowed(lint)) {
+            self.diagnostics.push(diagnostic.with_code(lint.name()));
+        }
+    }
+}
+
+/// Get the parameters of a key with their spans.
+///
+/// `exprs` are the expressions the key was made from.
+fn params(key: &EvalIdents, exprs: &[SemNodeExpr]) -> Vec<(Symbol, Span)> {
+    let exprs = exprs
+        .iter()
+        .filter(|expr| !matches!(expr.value, SemNodeExprKind::Error { .. }));
+
+    key.iter()
+        .zip(exprs)
+        .flat_map(|(ident, expr)| match (ident, &expr.value) {
+            (EvalIdentsKind::Param(param), _) => vec![(*param, expr.span.clone())],
+            (EvalIdentsKind::Inner(inner), SemNodeExprKind::Inner(exprs)) => params(inner, exprs),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Get the span covering expressions.
+fn exprs_span(exprs: &[SemNodeExpr]) -> Option<Span> {
+    Some(exprs.first()?.span.to(&exprs.last()?.span))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{check, Severity};
+
+    fn analyze_src(src: &str, severity: Severity) -> Vec<(Option<String>, String, usize)> {
+        let mut interner = Interner::new();
+        let result = check(src, &mut interner);
+        assert!(result.diagnostics.is_empty());
+        Analyzer::new(&mut interner)
+            .analyze_checked(&result)
+            .iter()
+            .filter(|x| x.severity == severity)
+            .map(|x| (x.code.clone(), x.msg.clone(), x.primary.span.start.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_errors() {
+        let src = "0 {}\n+ {}\n$a + $b {}\nx {}\nx { 0 + y }\n$p $q {}\nh {}\nh $v { i {} }\nz {}\nz { (0) + (n) }";
+        assert_eq!(
+            analyze_src(src, Severity::Error)
+                .into_iter()
+                .map(|(_, msg, line)| (msg, line))
+                .collect::<Vec<_>>(),
+            [
+                ("identifiers not found: 0 + y".to_string(), 5),
+                (
+                    "a definition must have at least one non-parameter identifier".to_string(),
+                    6
+                ),
+                (
+                    "definition with a body must have expressions".to_string(),
+                    8
+                ),
+                ("identifiers not found: (0) + (n)".to_string(), 10),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_analyze_bodies_after_scope() {
+        let src = "0 {}\n+ {}\nf {}\nf $v {\n    g {}\n    g { $v }\n    later + g\n}\n$a + $b {}\nlater {}\ndbg! { f 0 }";
+        assert!(analyze_src(src, Severity::Error).is_empty());
+        assert!(analyze_src(src, Severity::Warning).is_empty());
+
+        let demo = include_str!("../../examples/demo.deck");
+        assert!(analyze_src(demo, Severity::Error).is_empty());
+    }
+
+    #[test]
+    fn test_analyze_numeric() {
+        let mut interner = Interner::new();
+        let mut natives = EvalNatives::with_builtins(&mut interner);
+        natives.register_numeric(&mut interner);
+        let src = "x {}\ndbg! { (x * 2) < -1 }\ndbg! { 007 }";
+        let nodes = check(src, &mut interner).nodes;
+        let diagnostics = Analyzer::with_natives(&natives, &interner).analyze(&nodes);
+        let msgs = diagnostics
+            .iter()
+            .map(|x| x.msg.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(msgs, ["identifiers not found: 007"]);
+    }
+
+    #[test]
+    fn test_analyze_eager_natives() {
+        // Natives that evaluate their arguments fail at run time when they do not resolve
+        for (src, fails) in [
+            ("f {}\nf $n { $n * 10 }\ndbg! { f 4 }", true),
+            (
+                "f {}\nf $n {\n    y {}\n    $n * 10\n}\ndbg! { f 4 }",
+                false,
+            ),
+            ("x {}\ndbg! { (x * 2) < -1 }", false),
+            ("y {}\ny { dbg! (z) }\nw {}\nw { y }", true),
+            ("z {}\ny {}\ny { dbg! (z) }\nw {}\nw { y }", false),
+        ] {
+            let mut interpreter = crate::Interpreter::new();
+            interpreter.register_numeric();
+            interpreter.set_output(crate::EvalCapture::new());
+            // A clone keeps the symbols of the natives, and interns the source after them
+            let mut interner = interpreter.interner().clone();
+            let result = check(src, &mut interner);
+            let analyzer = Analyzer::with_natives(interpreter.stack().natives(), &interner);
+            let diagnostics = analyzer.analyze_checked(&result);
+            assert_eq!(diagnostics.has_errors(), fails, "{src:?}: {diagnostics:?}");
+            assert_eq!(
+                interpreter.load_source("test", src).is_err(),
+                fails,
+                "{src:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_analyze_lints() {
+        let src = "0 {}\n+ {}\n$a + $b {}\nx {}\nx { 0 }\nx { 0 + 0 }\nunused {}\nf {}\nf $v $w {\n    g {}\n    g { $v }\n    g\n}\ndbg! { f x 0 }";
+        let lint = |lint: Lint, msg: &str, line| (Some(lint.to_string()), msg.to_string(), line);
+        assert_eq!(
+            analyze_src(src, Severity::Warning),
+            [
+                lint(Lint::ShadowedSignature, "shadowed signature: x", 5),
+                lint(Lint::UnusedDefinition, "unused definition: unused", 7),
+                lint(Lint::UnusedParameter, "unused parameter: $w", 9),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_analyze_lint_allows() {
+        let src = "// allow! unused_definition unknown\n0 {}\nf {}\nf $v {\n    g {}\n    0\n}\n";
+        let expected = [
+            (None, "unknown lint: unknown".to_string(), 1),
+            (
+                Some(Lint::UnusedParameter.to_string()),
+                "unused parameter: $v".to_string(),
+                4,
+            ),
+        ];
+        assert_eq!(analyze_src(src, Severity::Warning), expected);
+
+        let mut interner = Interner::new();
+        let result = check(src, &mut interner);
+        let diagnostics = Analyzer::new(&mut interner).analyze_checked(&result);
+        let unknown = &diagnostics.iter().next().unwrap().primary.span;
+        assert_eq!(&src[unknown.start.idx..unknown.end_idx()], "unknown");
+        assert_eq!(unknown.start.col, 29);
+
+        let src = src.replace(
+            "// allow! unused_definition unknown",
+            "{ allow! unused_definition unknown }",
+        );
+        assert_eq!(analyze_src(&src, Severity::Warning), expected);
+
+        let src = "// allow!unused_definition\n// not allow! unused_definition\n0 {}\n";
+        assert_eq!(analyze_src(src, Severity::Warning).len(), 1);
+    }
+    #[test]
+    fn test_analyze_files() {
+        // Later files use the definitions of earlier ones, each with its own allows
+        let files = [
+            "// allow! unused_definition\n1 {}\nx {}\n+ {}\n$a + $b {}",
+            "two {}\ntwo { 1 + 1 }\ny {}\ndbg! { two }",
+        ];
+        let mut interner = Interner::new();
+        let results = files
+            .iter()
+            .enumerate()
+            .map(|(idx, src)| crate::check_with_id(src, SrcId(idx + 1), &mut interner))
+            .collect::<Vec<_>>();
+        let mut analyzer = Analyzer::new(&mut interner);
+        for result in &results {
+            analyzer.analyze_file(result);
+        }
+        let diagnostics = analyzer
+            .finish()
+            .iter()
+            .map(|x| (x.msg.clone(), x.primary.span.src))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            [("unused definition: y".to_string(), SrcId(2))]
+        );
+    }
+}
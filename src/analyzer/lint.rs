@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use strum_macros::{EnumString, IntoStaticStr};
+
+use crate::{
+    Interner, SemNode, SemNodeExpr, SemNodeExprKind, SemNodeKind, Span, SpanPos, Token, TokenKind,
+};
+
+/// Lint reported as a warning by the analyzer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Lint {
+    /// A definition that is never used.
+    UnusedDefinition,
+
+    /// A parameter that is never used in the body of its definition.
+    UnusedParameter,
+
+    /// A definition shadowed by a later definition before it is used.
+    ShadowedSignature,
+}
+
+impl Lint {
+    /// Get the stable name of the lint.
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Directive that disables lints in a file.
+pub const LINT_ALLOW_DIRECTIVE: &str = "allow!";
+
+/// Lints allowed by the directives of a file.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LintAllows {
+    pub allowed: HashSet<Lint>,
+
+    /// Names in directives that are not lints, with their spans.
+    pub unknown: Vec<(String, Span)>,
+}
+
+impl LintAllows {
+    /// Collect the lints allowed by the directives of a file.
+    ///
+    /// A directive is a comment starting with `allow!`, followed by lint names:
+    /// `// allow! unused_parameter shadowed_signature`.
+    pub fn from_comments(comments: &[Token]) -> Self {
+        let mut allows = Self::default();
+
+        for comment in comments {
+            let TokenKind::Comment(text) = &comment.value else {
+                continue;
+            };
+            let text = text.as_str();
+
+            let mut words = text
+                .split_whitespace()
+                .map(|word| (word, word.as_ptr() as usize - text.as_ptr() as usize));
+            if words.next().map(|(word, _)| word) != Some(LINT_ALLOW_DIRECTIVE) {
+                continue;
+            }
+
+            for (name, offset) in words {
+                match Lint::from_str(name) {
+                    Ok(lint) => {
+                        allows.allowed.insert(lint);
+                    }
+                    Err(_) => {
+                        // The text starts after the `//` of the comment, on the same line
+                        let start = &comment.span.start;
+                        let span = Span {
+                            src: comment.span.src,
+                            start: SpanPos {
+                                line: start.line,
+                                col: start.col + 2 + text[..offset].chars().count(),
+                                idx: start.idx + 2 + offset,
+                            },
+                            len: name.len(),
+                        };
+                        allows.unknown.push((name.to_string(), span));
+                    }
+                }
+            }
+        }
+
+        allows
+    }
+
+    /// Collect the lints allowed by the top-level empty-signature definitions
+    /// of a file.
+    ///
+    /// This is the directive form from before `//` comments, kept for
+    /// compatibility: `{ allow! unused_parameter shadowed_signature }`.
+    ///
+    /// The identifiers of the nodes resolve through `interner`.
+    pub fn from_nodes(nodes: &[SemNode], interner: &Interner) -> Self {
+        let mut allows = Self::default();
+
+        for node in nodes {
+            let SemNodeKind::Def {
+                idents,
+                body,
+                exprs,
+            } = &node.value
+            else {
+                continue;
+            };
+
+            let [SemNodeExpr {
+                value: SemNodeExprKind::Ident(directive),
+                ..
+            }, names @ ..] = &exprs[..]
+            else {
+                continue;
+            };
+
+            if !idents.is_empty()
+                || !body.is_empty()
+                || interner.resolve(*directive) != LINT_ALLOW_DIRECTIVE
+            {
+                continue;
+            }
+
+            for name in names {
+                match &name.value {
+                    SemNodeExprKind::Ident(ident) => {
+                        let ident = interner.resolve(*ident);
+                        match Lint::from_str(ident) {
+                            Ok(lint) => {
+                                allows.allowed.insert(lint);
+                            }
+                            Err(_) => allows.unknown.push((ident.to_string(), name.span.clone())),
+                        }
+                    }
+                    _ => allows
+                        .unknown
+                        .push((String::from("(...)"), name.span.clone())),
+                }
+            }
+        }
+
+        allows
+    }
+
+    /// Add the lints allowed by other directives.
+    pub fn extend(&mut self, other: LintAllows) {
+        self.allowed.extend(other.allowed);
+        self.unknown.extend(other.unknown);
+    }
+
+    /// Check if a lint is allowed.
+    pub fn is_allowed(&self, lint: Lint) -> bool {
+        self.allowed.contains(&lint)
+    }
+}
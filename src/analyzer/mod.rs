@@ -0,0 +1,7 @@
+#[allow(clippy::module_inception)]
+mod analyzer;
+pub use analyzer::*;
+mod scope;
+pub use scope::*;
+mod lint;
+pub use lint::*;
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use crate::{
+    parse_int, EvalDefValue, EvalIdents, EvalIdentsExtensions, EvalIdentsKind, EvalNatives,
+    Interner, Span, Symbol,
+};
+
+/// Kind of an analyzed definition.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum AnalyzerDefKind {
+    /// Base: a definition without body or expressions
+    Base,
+
+    /// Reference: a definition with expressions only
+    Ref,
+
+    /// Node: a definition with a body
+    Node,
+
+    /// Parameter: a parameter bound in a body
+    Param,
+
+    /// Native: a function defined by the host
+    Native,
+
+    /// Eager native: a function defined by the host that evaluates its arguments
+    EagerNative,
+}
+
+impl AnalyzerDefKind {
+    /// Check if resolving the definition resolves its arguments,
+    /// so they must resolve too.
+    pub fn resolves_args(&self) -> bool {
+        matches!(self, AnalyzerDefKind::Base | AnalyzerDefKind::EagerNative)
+    }
+}
+
+/// Analyzed definition.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct AnalyzerDef {
+    pub key: EvalIdents,
+    pub kind: AnalyzerDefKind,
+
+    /// Span of the signature, or of the parameter.
+    pub span: Span,
+
+    /// Whether the definition has been resolved.
+    pub used: bool,
+}
+
+impl AnalyzerDef {
+    /// Check if the definition only declares its signature,
+    /// to be redefined later: `x {}`.
+    pub fn is_declaration(&self) -> bool {
+        self.kind == AnalyzerDefKind::Base
+            && self
+                .key
+                .iter()
+                .all(|x| matches!(x, EvalIdentsKind::Expr(_) | EvalIdentsKind::Int(_)))
+    }
+}
+
+/// Analyzer definition stack.
+///
+/// Mirrors the scoping rules of [`crate::EvalStack`] without values.
+#[derive(Debug, Clone)]
+pub struct AnalyzerStack {
+    stack: Vec<Vec<AnalyzerDef>>,
+
+    /// Signatures of the native functions, latest first.
+    natives: Vec<(EvalIdents, AnalyzerDefKind)>,
+    numeric: bool,
+}
+
+impl AnalyzerStack {
+    /// Create a new analyzer stack with a root scope and the builtin native
+    /// functions, interned into `interner`.
+    pub fn new(interner: &mut Interner) -> Self {
+        Self::with_natives(&EvalNatives::with_builtins(interner))
+    }
+
+    /// Create a new analyzer stack with a root scope and native functions.
+    pub fn with_natives(natives: &EvalNatives) -> Self {
+        Self {
+            stack: vec![vec![]],
+            natives: natives
+                .defs()
+                .map(|def| match &def.value {
+                    EvalDefValue::Native(f) if f.eager_args() => {
+                        (def.key.clone(), AnalyzerDefKind::EagerNative)
+                    }
+                    _ => (def.key.clone(), AnalyzerDefKind::Native),
+                })
+                .collect(),
+            numeric: natives.numeric(),
+        }
+    }
+
+    /// Push a new scope onto the stack.
+    pub fn push_scope(&mut self) {
+        self.stack.push(vec![]);
+    }
+
+    /// Pop a scope from the stack.
+    pub fn pop_scope(&mut self) -> Option<Vec<AnalyzerDef>> {
+        self.stack.pop()
+    }
+
+    /// Push a new definition onto the stack.
+    pub fn push_def(&mut self, key: EvalIdents, kind: AnalyzerDefKind, span: Span) {
+        self.stack
+            .last_mut()
+            .expect("scope is in stack")
+            .push(AnalyzerDef {
+                key,
+                kind,
+                span,
+                used: false,
+            });
+    }
+
+    /// Check if identifiers resolve, without marking any definition as used.
+    pub fn contains(&self, idents: &EvalIdents) -> bool {
+        self.stack
+            .iter()
+            .flatten()
+            .map(|def| &def.key)
+            .chain(self.natives.iter().map(|(key, _)| key))
+            .any(|key| matches!(key.matches(idents), Ok(Some(_))))
+            || self.is_literal(idents)
+    }
+
+    /// Resolve identifiers to the definition the evaluator would use,
+    /// marking it as used.
+    ///
+    /// Native functions are resolved after every scope, then literals.
+    pub fn resolve(
+        &mut self,
+        idents: &EvalIdents,
+    ) -> Option<(AnalyzerDefKind, HashMap<Symbol, EvalIdentsKind>)> {
+        let def = self.stack.iter_mut().rev().find_map(|scope| {
+            scope.iter_mut().rev().find_map(|def| {
+                let args = def.key.matches(idents).ok().flatten()?;
+                def.used = true;
+                Some((def.kind, args))
+            })
+        });
+
+        def.or_else(|| {
+            self.natives.iter().find_map(|(key, kind)| {
+                let args = key.matches(idents).ok().flatten()?;
+                Some((*kind, args))
+            })
+        })
+        .or_else(|| {
+            self.is_literal(idents)
+                .then(|| (AnalyzerDefKind::Native, HashMap::new()))
+        })
+    }
+
+    /// Check if identifiers are a literal: a string, or an integer literal
+    /// of the numeric extension.
+    fn is_literal(&self, idents: &EvalIdents) -> bool {
+        match idents.as_slice() {
+            [EvalIdentsKind::Str(_)] => true,
+            _ => self.numeric && parse_int(idents).is_some(),
+        }
+    }
+
+    /// Shadow the unused definitions that `key` fully shadows, and return the
+    /// ones of the current scope.
+    ///
+    /// Declarations are meant to be redefined, so shadowing one uses it.
+    pub fn shadow(&mut self, key: &EvalIdents) -> Vec<AnalyzerDef> {
+        let current = self.stack.len() - 1;
+        let mut shadowed = vec![];
+
+        for (i, scope) in self.stack.iter_mut().enumerate() {
+            for def in scope
+                .iter_mut()
+                .filter(|def| !def.used && key.subsumes(&def.key))
+            {
+                if def.is_declaration() {
+                    def.used = true;
+                } else if i == current {
+                    def.used = true;
+                    shadowed.push(def.clone());
+                }
+            }
+        }
+
+        shadowed
+    }
+}
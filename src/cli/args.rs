@@ -0,0 +1,226 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use super::SrcInput;
+use deck::unstable::EvalNatives;
+use deck::{Breakpoint, EvalDebugOption, EvalLimits, Interner, RenderStyle};
+
+/// Command-line arguments.
+#[derive(Debug, Clone, Parser)]
+#[command(
+    name = "deck",
+    version,
+    about = "Run deck programs.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    /// When to color diagnostics.
+    #[arg(long, value_enum, global = true, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+}
+
+impl Args {
+    /// Get the command, defaulting to [`Command::Run`].
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Run(self.run))
+    }
+}
+
+/// When to color output.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// Color if the output is a terminal.
+    #[default]
+    Auto,
+
+    /// Always color.
+    Always,
+
+    /// Never color.
+    Never,
+}
+
+impl ColorChoice {
+    /// Get the render style for an output.
+    pub fn style(self, output: &impl IsTerminal) -> RenderStyle {
+        match self {
+            ColorChoice::Auto if output.is_terminal() => RenderStyle::Ansi,
+            ColorChoice::Always => RenderStyle::Ansi,
+            _ => RenderStyle::Plain,
+        }
+    }
+}
+
+/// Subcommands.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Run source files.
+    Run(RunArgs),
+
+    /// Check source files without running them.
+    Check(CheckArgs),
+
+    /// Start an interactive session.
+    Repl(ReplArgs),
+
+    /// Step through a source file.
+    Debug(DebuggerArgs),
+
+    /// Format source files in place.
+    Fmt(FmtArgs),
+}
+
+/// Arguments of [`Command::Run`].
+#[derive(Debug, Clone, clap::Args)]
+pub struct RunArgs {
+    /// Source files to run, `-` reads from stdin.
+    #[arg(required = true, value_name = "FILE")]
+    pub files: Vec<SrcInput>,
+
+    #[command(flatten)]
+    pub debug: DebugArgs,
+
+    #[command(flatten)]
+    pub limits: LimitArgs,
+
+    #[command(flatten)]
+    pub extensions: ExtensionArgs,
+}
+
+/// Arguments of [`Command::Check`].
+#[derive(Debug, Clone, clap::Args)]
+pub struct CheckArgs {
+    /// Source files to check, `-` reads from stdin.
+    #[arg(required = true, value_name = "FILE")]
+    pub files: Vec<SrcInput>,
+
+    #[command(flatten)]
+    pub extensions: ExtensionArgs,
+}
+
+/// Arguments of [`Command::Repl`].
+#[derive(Debug, Clone, clap::Args)]
+pub struct ReplArgs {
+    #[command(flatten)]
+    pub debug: DebugArgs,
+
+    #[command(flatten)]
+    pub limits: LimitArgs,
+
+    #[command(flatten)]
+    pub extensions: ExtensionArgs,
+}
+
+/// Arguments of [`Command::Debug`].
+#[derive(Debug, Clone, clap::Args)]
+pub struct DebuggerArgs {
+    /// Source file to debug, commands are read from stdin.
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Breakpoints to start with, a line number or a signature like `f $x`.
+    #[arg(short, long = "break", value_name = "BREAKPOINT")]
+    pub breakpoints: Vec<Breakpoint>,
+
+    /// Run until the first breakpoint instead of pausing at the start.
+    #[arg(short, long = "continue")]
+    pub run: bool,
+
+    #[command(flatten)]
+    pub limits: LimitArgs,
+
+    #[command(flatten)]
+    pub extensions: ExtensionArgs,
+}
+
+/// Arguments of [`Command::Fmt`].
+#[derive(Debug, Clone, clap::Args)]
+pub struct FmtArgs {
+    /// Source files to format, `-` formats stdin to stdout.
+    #[arg(required = true, value_name = "FILE")]
+    pub files: Vec<SrcInput>,
+
+    /// Report files that are not formatted instead of formatting them.
+    #[arg(long)]
+    pub check: bool,
+}
+
+/// Evaluation debug switches.
+#[derive(Debug, Clone, clap::Args)]
+pub struct DebugArgs {
+    /// Print the definition stack before each evaluation step.
+    #[arg(long)]
+    pub debug_stack: bool,
+
+    /// Print each node before it is evaluated.
+    #[arg(long)]
+    pub debug_call: bool,
+}
+
+impl DebugArgs {
+    /// Get the evaluation debug options.
+    pub fn options(&self) -> EvalDebugOption {
+        let mut options = EvalDebugOption::NONE;
+        options.set(EvalDebugOption::STACK, self.debug_stack);
+        options.set(EvalDebugOption::CALL, self.debug_call);
+        options
+    }
+}
+
+/// Evaluation limits, `0` is unlimited.
+#[derive(Debug, Clone, clap::Args)]
+pub struct LimitArgs {
+    /// Maximum number of reduction steps per source.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub max_steps: usize,
+
+    /// Maximum number of expressions evaluated inside each other.
+    #[arg(long, value_name = "N", default_value_t = EvalLimits::DEFAULT_DEPTH)]
+    pub max_depth: usize,
+
+    /// Maximum number of identifiers in an expression.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub max_expr_size: usize,
+}
+
+impl LimitArgs {
+    /// Get the evaluation limits.
+    pub fn limits(&self) -> EvalLimits {
+        let limit = |max| match max {
+            0 => None,
+            max => Some(max),
+        };
+        EvalLimits {
+            steps: limit(self.max_steps),
+            depth: limit(self.max_depth),
+            expr_size: limit(self.max_expr_size),
+        }
+    }
+}
+
+/// Language extensions.
+#[derive(Debug, Clone, clap::Args)]
+pub struct ExtensionArgs {
+    /// Treat integer literals as values, with builtin `+ - * / % < =` on them.
+    #[arg(long)]
+    pub numeric: bool,
+}
+
+impl ExtensionArgs {
+    /// Get the native functions of the enabled extensions, interned into `interner`.
+    pub fn natives(&self, interner: &mut Interner) -> EvalNatives {
+        let mut natives = EvalNatives::with_builtins(interner);
+        if self.numeric {
+            natives.register_numeric(interner);
+        }
+        natives
+    }
+}
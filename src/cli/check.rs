@@ -0,0 +1,106 @@
+use std::process::ExitCode;
+
+use super::{CheckArgs, ColorChoice, ExtensionArgs, SrcInput};
+use deck::unstable::{check_with_id, Analyzer};
+use deck::{Interner, RenderStyle, Sources, SrcId};
+
+/// Check every source file without evaluating it.
+///
+/// The files share a definition stack in order, like `run` loads them, so a
+/// file can use the definitions of the files before it.
+pub fn check(args: &CheckArgs, color: ColorChoice) -> ExitCode {
+    let style = color.style(&std::io::stderr());
+
+    if SrcInput::is_stdin_repeated(&args.files) {
+        eprintln!("error: {} is given more than once", SrcInput::Stdin);
+        return ExitCode::FAILURE;
+    }
+
+    let mut sources = Sources::new();
+    for input in &args.files {
+        match input.read() {
+            Ok(src) => sources.push(input.to_string(), src),
+            Err(err) => {
+                eprintln!("error: cannot read {input}: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+    }
+
+    let (output, has_errors) = check_sources(&sources, &args.extensions, style);
+    eprint!("{output}");
+    match has_errors {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// Check sources in order, and render their diagnostics.
+///
+/// Sources are only analyzed if none of them has syntax errors, since the
+/// definitions of a source with errors are missing from the ones after it.
+///
+/// Returns the rendered diagnostics, and whether any of them is an error.
+fn check_sources(
+    sources: &Sources,
+    extensions: &ExtensionArgs,
+    style: RenderStyle,
+) -> (String, bool) {
+    let mut interner = Interner::new();
+    let natives = extensions.natives(&mut interner);
+
+    let mut results = vec![];
+    let mut output = vec![];
+    let mut has_errors = false;
+    for id in (1..=sources.len()).map(SrcId) {
+        let (name, src) = sources.get(id).expect("source is pushed");
+        let result = check_with_id(src, id, &mut interner);
+        if !result.diagnostics.is_empty() {
+            output.push(result.diagnostics.render(name, src, style));
+        }
+        has_errors |= result.diagnostics.has_errors();
+        results.push(result);
+    }
+
+    if !has_errors {
+        let mut analyzer = Analyzer::with_natives(&natives, &interner);
+        for result in &results {
+            analyzer.analyze_file(result);
+        }
+
+        let diagnostics = analyzer.finish();
+        if !diagnostics.is_empty() {
+            output.push(diagnostics.render_with(|id| sources.get(id), style));
+        }
+        has_errors |= diagnostics.has_errors();
+    }
+
+    (output.join("\n"), has_errors)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(files: &[(&str, &str)]) -> (String, bool) {
+        let mut sources = Sources::new();
+        for (name, src) in files {
+            sources.push(*name, *src);
+        }
+        let extensions = ExtensionArgs { numeric: false };
+        check_sources(&sources, &extensions, RenderStyle::Plain)
+    }
+
+    #[test]
+    fn test_check_files_in_order() {
+        let a = ("a.deck", "1 {}\n+ {}\n$a + $b {}");
+        let b = ("b.deck", "two {}\ntwo { 1 + 1 }\ndbg! { two }");
+        assert_eq!(check(&[a, b]), (String::new(), false));
+
+        // Definitions are only visible to the files after them
+        let (output, has_errors) = check(&[b, a]);
+        assert!(has_errors);
+        assert!(output.contains("identifiers not found: 1 + 1\n --> b.deck:2:7"));
+        assert!(output.contains("unused definition: $a + $b\n --> a.deck:3:1"));
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+use super::{ColorChoice, DebuggerArgs};
+use deck::unstable::check;
+use deck::{
+    Breakpoint, DebugCommand, DebugFrontend, DebugPause, DebugPauseReason, Debugger, EvalError,
+    EvalEvent, EvalOutput, EvalStack, Interner, Interpreter, InterpreterError, SimpleDisplay,
+};
+
+const HELP: &str = "\
+s, step             step into the next stop
+n, next             step over calls
+o, out              step out of the current body
+c, continue         run until a breakpoint
+b, break <bp>       add a breakpoint, a line number or a signature like `f $x`
+d, delete <n>       remove a breakpoint
+breakpoints         list the breakpoints
+where               show the current location
+args                show the parameter bindings of the current resolution
+locals              show the definitions of the innermost scope
+scopes              show the definitions of every scope
+h, help             show this message
+q, quit             exit the debugger";
+
+/// Debug a source file with commands read from stdin.
+pub fn debug(args: &DebuggerArgs, color: ColorChoice) -> ExitCode {
+    let style = color.style(&std::io::stderr());
+    let name = args.file.display().to_string();
+    let src = match std::fs::read_to_string(&args.file) {
+        Ok(src) => src,
+        Err(err) => {
+            eprintln!("error: cannot read {name}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // The interpreter checks again when loading, with its own interner
+    let result = check(&src, &mut Interner::new());
+    if !result.diagnostics.is_empty() {
+        eprint!("{}", result.diagnostics.render(&name, &src, style));
+    }
+    if result.diagnostics.has_errors() {
+        return ExitCode::FAILURE;
+    }
+
+    // A stdin lock cannot move into the interpreter, which must stay `Send`
+    let reader = std::io::BufReader::new(std::io::stdin());
+    let console = DebugConsole::new(&name, &src, reader, std::io::stdout());
+    let mut debugger = Debugger::new(console);
+    for breakpoint in &args.breakpoints {
+        debugger.add_breakpoint(breakpoint.clone());
+    }
+    debugger.set_paused(!args.run);
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(args.limits.limits());
+    if args.extensions.numeric {
+        interpreter.register_numeric();
+    }
+    interpreter.add_observer(debugger);
+    match interpreter.load_source(name, src) {
+        Ok(())
+        | Err(InterpreterError::Eval {
+            error: EvalError::Aborted { .. },
+            ..
+        }) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprint!("{}", err.render(style));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Debugger frontend reading commands from `reader`.
+#[derive(Debug)]
+pub struct DebugConsole<R, W> {
+    name: String,
+    src: String,
+    reader: R,
+    writer: W,
+
+    /// Whether the location must be shown, after resuming.
+    resumed: bool,
+
+    /// Whether the reader ended, so the program runs to the end.
+    detached: bool,
+}
+
+impl<R, W> DebugConsole<R, W>
+where
+    R: BufRead + std::fmt::Debug,
+    W: Write + std::fmt::Debug,
+{
+    /// Create a new debugger console for a source.
+    pub fn new(name: &str, src: &str, reader: R, writer: W) -> Self {
+        Self {
+            name: name.to_string(),
+            src: src.to_string(),
+            reader,
+            writer,
+            resumed: true,
+            detached: false,
+        }
+    }
+
+    /// Read commands until one for the debugger.
+    fn prompt(&mut self, pause: &DebugPause, stack: &EvalStack) -> std::io::Result<DebugCommand> {
+        if std::mem::take(&mut self.resumed) {
+            self.write_location(pause, stack)?;
+        }
+
+        loop {
+            write!(self.writer, "(deck) ")?;
+            self.writer.flush()?;
+
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                writeln!(self.writer)?;
+                self.detached = true;
+                return Ok(DebugCommand::Continue);
+            }
+
+            let (command, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let rest = rest.trim();
+            let command = match (command, rest) {
+                ("", _) => continue,
+                ("s" | "step", "") => DebugCommand::StepIn,
+                ("n" | "next", "") => DebugCommand::StepOver,
+                ("o" | "out", "") => DebugCommand::StepOut,
+                ("c" | "continue", "") => DebugCommand::Continue,
+                ("b" | "break", breakpoint) => match breakpoint.parse::<Breakpoint>() {
+                    Ok(breakpoint) => {
+                        let idx = pause.breakpoints.len();
+                        writeln!(self.writer, "breakpoint {idx}: {breakpoint}")?;
+                        return Ok(DebugCommand::Break(breakpoint));
+                    }
+                    Err(err) => {
+                        writeln!(self.writer, "error: {err}")?;
+                        continue;
+                    }
+                },
+                ("d" | "delete", idx) => match idx.parse::<usize>() {
+                    Ok(idx) if idx < pause.breakpoints.len() => {
+                        writeln!(self.writer, "deleted breakpoint {idx}")?;
+                        return Ok(DebugCommand::Delete(idx));
+                    }
+                    _ => {
+                        writeln!(self.writer, "error: no breakpoint '{idx}'")?;
+                        continue;
+                    }
+                },
+                ("breakpoints", "") => {
+                    if pause.breakpoints.is_empty() {
+                        writeln!(self.writer, "no breakpoints")?;
+                    }
+                    for (idx, breakpoint) in pause.breakpoints.iter().enumerate() {
+                        writeln!(self.writer, "{idx}: {breakpoint}")?;
+                    }
+                    continue;
+                }
+                ("where", "") => {
+                    self.write_location(pause, stack)?;
+                    continue;
+                }
+                ("args", "") => {
+                    let EvalEvent::Resolved { args, .. } = pause.event else {
+                        writeln!(self.writer, "no resolution here")?;
+                        continue;
+                    };
+                    if args.is_empty() {
+                        writeln!(self.writer, "no arguments")?;
+                    }
+                    let interner = stack.interner();
+                    let args = args
+                        .iter()
+                        .map(|(param, value)| (interner.resolve(*param), value))
+                        .collect::<BTreeMap<_, _>>();
+                    for (param, value) in args {
+                        writeln!(
+                            self.writer,
+                            "{param} = {}",
+                            vec![value.clone()].simple_display(interner)
+                        )?;
+                    }
+                    continue;
+                }
+                ("locals", "") => {
+                    let scope = stack.scopes().last().unwrap_or_default();
+                    self.write_scope(scope, stack.interner())?;
+                    continue;
+                }
+                ("scopes", "") => {
+                    for (idx, scope) in stack.scopes().enumerate() {
+                        writeln!(self.writer, "scope {idx}:")?;
+                        self.write_scope(scope, stack.interner())?;
+                    }
+                    continue;
+                }
+                ("h" | "help", "") => {
+                    writeln!(self.writer, "{HELP}")?;
+                    continue;
+                }
+                ("q" | "quit", "") => DebugCommand::Quit,
+                (command, _) => {
+                    writeln!(self.writer, "error: unknown command '{command}', try help")?;
+                    continue;
+                }
+            };
+
+            self.resumed = true;
+            return Ok(command);
+        }
+    }
+
+    /// Write the location of a pause.
+    fn write_location(&mut self, pause: &DebugPause, stack: &EvalStack) -> std::io::Result<()> {
+        let span = pause.event.span();
+        let reason = match pause.reason {
+            DebugPauseReason::Step => "paused".to_string(),
+            DebugPauseReason::Breakpoint(idx) => format!("breakpoint {idx}"),
+        };
+        writeln!(
+            self.writer,
+            "{reason} at {}:{}:{}, depth {}",
+            self.name, span.start.line, span.start.col, pause.depth,
+        )?;
+
+        let line = self
+            .src
+            .lines()
+            .nth(span.start.line - 1)
+            .unwrap_or_default();
+        writeln!(self.writer, "{} | {}", span.start.line, line.trim_end())?;
+
+        if let EvalEvent::Resolved { idents, key, .. } = pause.event {
+            writeln!(
+                self.writer,
+                "resolving '{}' with '{}'",
+                idents.simple_display(stack.interner()),
+                key.simple_display(stack.interner()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the definitions of a scope.
+    fn write_scope(
+        &mut self,
+        scope: &[deck::EvalStackDef],
+        interner: &Interner,
+    ) -> std::io::Result<()> {
+        for def in scope {
+            writeln!(
+                self.writer,
+                "    {} = {}",
+                def.key.simple_display(interner),
+                def.value.simple_display(interner),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<R, W> DebugFrontend for DebugConsole<R, W>
+where
+    R: BufRead + std::fmt::Debug,
+    W: Write + std::fmt::Debug,
+{
+    fn pause(
+        &mut self,
+        pause: &DebugPause,
+        stack: &EvalStack,
+        _: &mut dyn EvalOutput,
+    ) -> DebugCommand {
+        if self.detached {
+            return DebugCommand::Continue;
+        }
+        self.prompt(pause, stack).unwrap_or(DebugCommand::Continue)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Writer shared with the test, since the console is moved into the interpreter.
+    #[derive(Debug, Clone, Default)]
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const SRC: &str = "1 {}\nf {}\nf $x {\n    y {}\n    y { $x }\n    y\n}\nz {}\nz { f 1 }";
+
+    fn run(input: &str) -> (String, Result<(), InterpreterError>) {
+        let writer = SharedWriter::default();
+        let reader = std::io::Cursor::new(input.to_string());
+        let console = DebugConsole::new("test", SRC, reader, writer.clone());
+        let mut interpreter = Interpreter::new();
+        interpreter.add_observer(Debugger::new(console));
+        let result = interpreter.load_source("test", SRC);
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        (output, result)
+    }
+
+    fn is_aborted(result: &Result<(), InterpreterError>) -> bool {
+        matches!(
+            result,
+            Err(InterpreterError::Eval {
+                error: EvalError::Aborted { .. },
+                ..
+            })
+        )
+    }
+
+    #[test]
+    fn test_debug_console_steps() {
+        let (output, result) = run("s\nn\nbogus\no\n");
+        assert!(result.is_ok());
+        assert_eq!(
+            output,
+            "\
+paused at test:1:3, depth 0
+1 | 1 {}
+(deck) paused at test:2:3, depth 0
+2 | f {}
+(deck) paused at test:3:6, depth 0
+3 | f $x {
+(deck) error: unknown command 'bogus', try help
+(deck) ",
+        );
+    }
+
+    #[test]
+    fn test_debug_console_breakpoints() {
+        let (output, result) = run("b\nb 0\nb 5\nb f\nbreakpoints\nc\nargs\nd 0\nd 3\nwhere\nc\n");
+        assert!(result.is_ok());
+        assert_eq!(
+            output,
+            "\
+paused at test:1:3, depth 0
+1 | 1 {}
+(deck) error: expected a line number or a signature
+(deck) error: line numbers start at 1
+(deck) breakpoint 0: line 5
+(deck) breakpoint 1: f
+(deck) 0: line 5
+1: f
+(deck) breakpoint 1 at test:3:1, depth 0
+3 | f $x {
+resolving 'f' with 'f'
+(deck) no arguments
+(deck) deleted breakpoint 0
+(deck) error: no breakpoint '3'
+(deck) breakpoint 0 at test:3:1, depth 0
+3 | f $x {
+resolving 'f' with 'f'
+(deck) ",
+        );
+    }
+
+    #[test]
+    fn test_debug_console_inspect() {
+        let (output, result) = run("b f $x\nc\nargs\ns\ns\nlocals\nscopes\nargs\nq\nc\n");
+        assert!(is_aborted(&result), "{result:?}");
+        assert_eq!(
+            output,
+            "\
+paused at test:1:3, depth 0
+1 | 1 {}
+(deck) breakpoint 0: f $x
+(deck) breakpoint 0 at test:9:3, depth 0
+9 | z { f 1 }
+resolving 'f 1' with 'f $x'
+(deck) $x = 1
+(deck) paused at test:4:7, depth 1
+4 |     y {}
+(deck) paused at test:5:7, depth 1
+5 |     y { $x }
+(deck)     $x = 1
+    y = {}
+(deck) scope 0:
+    1 = {}
+    f = {}
+    f $x = { ... }
+    z = {}
+scope 1:
+    $x = 1
+    y = {}
+(deck) no resolution here
+(deck) ",
+        );
+    }
+
+    #[test]
+    fn test_debug_console_quit() {
+        let (output, result) = run("quit\n");
+        assert!(is_aborted(&result), "{result:?}");
+        assert_eq!(output, "paused at test:1:3, depth 0\n1 | 1 {}\n(deck) ");
+    }
+}
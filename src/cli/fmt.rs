@@ -0,0 +1,62 @@
+use std::process::ExitCode;
+
+use super::{ColorChoice, FmtArgs, SrcInput};
+use deck::unstable::format_src;
+
+/// Format every source file, or check that they are formatted.
+///
+/// Files with syntax errors are reported and left unchanged.
+pub fn fmt(args: &FmtArgs, color: ColorChoice) -> ExitCode {
+    let style = color.style(&std::io::stderr());
+
+    if SrcInput::is_stdin_repeated(&args.files) {
+        eprintln!("error: {} is given more than once", SrcInput::Stdin);
+        return ExitCode::FAILURE;
+    }
+
+    let mut failed = false;
+    for input in &args.files {
+        let src = match input.read() {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: cannot read {input}: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let formatted = match format_src(&src) {
+            Ok(formatted) => formatted,
+            Err(diagnostics) => {
+                eprint!("{}", diagnostics.render(&input.to_string(), &src, style));
+                failed = true;
+                continue;
+            }
+        };
+
+        match (input, args.check) {
+            (_, true) if formatted != src => {
+                let line = src
+                    .lines()
+                    .zip(formatted.lines())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                eprintln!("{input}:{}: not formatted", line + 1);
+                failed = true;
+            }
+            (_, true) => {}
+            (SrcInput::Stdin, false) => print!("{formatted}"),
+            (SrcInput::File(path), false) if formatted != src => {
+                if let Err(err) = std::fs::write(path, formatted) {
+                    eprintln!("error: cannot write {input}: {err}");
+                    return ExitCode::FAILURE;
+                }
+            }
+            (SrcInput::File(_), false) => {}
+        }
+    }
+
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
@@ -0,0 +1,14 @@
+mod args;
+pub use args::*;
+mod run;
+pub use run::*;
+mod check;
+pub use check::*;
+mod src_input;
+pub use src_input::*;
+mod repl;
+pub use repl::*;
+mod debug;
+pub use debug::*;
+mod fmt;
+pub use fmt::*;
@@ -0,0 +1,294 @@
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+use super::{ColorChoice, ReplArgs, SrcInput};
+use deck::{EvalDebugOption, EvalLimits, Interpreter, InterpreterError, RenderStyle};
+
+const HELP: &str = "\
+:help               show this message
+:quit               exit the session
+:cancel             drop the unfinished input, so does an empty line
+:stack              print the definition stack
+:reset              clear all definitions
+:load <file>        evaluate a source file
+:debug call|stack   toggle a debug option";
+
+/// Start an interactive session on stdin and stdout.
+pub fn repl(args: &ReplArgs, color: ColorChoice) -> ExitCode {
+    let mut repl = Repl::new(
+        args.debug.options(),
+        args.limits.limits(),
+        args.extensions.numeric,
+        color.style(&std::io::stdout()),
+    );
+    match repl.run(std::io::stdin().lock(), std::io::stdout()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Interactive session.
+///
+/// The definition stack persists between inputs, so later inputs can use
+/// earlier definitions.
+#[derive(Debug)]
+pub struct Repl {
+    interpreter: Interpreter,
+    input: String,
+    style: RenderStyle,
+}
+
+/// Result of a meta-command.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum ReplFlow {
+    Continue,
+    Quit,
+}
+
+impl Repl {
+    /// Create a new session.
+    pub fn new(
+        debug_options: EvalDebugOption,
+        limits: EvalLimits,
+        numeric: bool,
+        style: RenderStyle,
+    ) -> Self {
+        let mut interpreter = Interpreter::new_with_debug(debug_options);
+        interpreter.set_limits(limits);
+        if numeric {
+            interpreter.register_numeric();
+        }
+        Self {
+            interpreter,
+            input: String::new(),
+            style,
+        }
+    }
+
+    /// Read and evaluate inputs until the end of `reader` or `:quit`.
+    pub fn run<R, W>(&mut self, mut reader: R, mut writer: W) -> std::io::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        loop {
+            write!(
+                writer,
+                "{}",
+                if self.input.is_empty() { "> " } else { ". " }
+            )?;
+            writer.flush()?;
+
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                writeln!(writer)?;
+                return Ok(());
+            }
+
+            if self.input.is_empty() && line.trim_start().starts_with(':') {
+                if self.meta(line.trim(), &mut writer)? == ReplFlow::Quit {
+                    return Ok(());
+                }
+                continue;
+            }
+
+            if !self.input.is_empty() && matches!(line.trim(), "" | ":cancel") {
+                self.input.clear();
+                continue;
+            }
+
+            self.input.push_str(&line);
+            if self.eval_input(&mut writer)? {
+                self.input.clear();
+            }
+        }
+    }
+
+    /// Evaluate the pending input.
+    ///
+    /// Returns `false` if the input is incomplete and more lines are needed.
+    fn eval_input<W>(&mut self, writer: &mut W) -> std::io::Result<bool>
+    where
+        W: Write,
+    {
+        if Interpreter::is_incomplete(&self.input) {
+            return Ok(false);
+        }
+
+        let src = std::mem::take(&mut self.input);
+        let result = self.interpreter.load_source("<repl>", src);
+        self.write_err(result, writer)?;
+
+        Ok(true)
+    }
+
+    /// Write the diagnostics of an error, if any.
+    fn write_err<W>(
+        &self,
+        result: Result<(), InterpreterError>,
+        writer: &mut W,
+    ) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => write!(writer, "{}", err.render(self.style)),
+        }
+    }
+
+    /// Run a meta-command.
+    fn meta<W>(&mut self, line: &str, writer: &mut W) -> std::io::Result<ReplFlow>
+    where
+        W: Write,
+    {
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, Some(arg.trim())),
+            None => (line, None),
+        };
+        match (command, arg) {
+            (":help", None) => writeln!(writer, "{HELP}")?,
+            (":quit", None) => return Ok(ReplFlow::Quit),
+            (":cancel", None) => {}
+            (":stack", None) => writeln!(writer, "{:#?}", self.interpreter.stack())?,
+            (":reset", None) => {
+                let interpreter = &self.interpreter;
+                *self = Self::new(
+                    interpreter.debug_options(),
+                    interpreter.limits(),
+                    interpreter.numeric(),
+                    self.style,
+                )
+            }
+            (":load", Some(path)) => match SrcInput::from(path) {
+                // Reading stdin to its end would also read the rest of the session
+                SrcInput::Stdin => writeln!(
+                    writer,
+                    "error: cannot load {}, it is the session input",
+                    SrcInput::Stdin
+                )?,
+                input => match input.read() {
+                    Ok(src) => {
+                        let result = self.interpreter.load_source(input.to_string(), src);
+                        self.write_err(result, writer)?;
+                    }
+                    Err(err) => writeln!(writer, "error: cannot read {input}: {err}")?,
+                },
+            },
+            (":debug", Some(option)) => {
+                let option = match option {
+                    "call" => EvalDebugOption::CALL,
+                    "stack" => EvalDebugOption::STACK,
+                    _ => {
+                        writeln!(writer, "error: unknown debug option '{option}'")?;
+                        return Ok(ReplFlow::Continue);
+                    }
+                };
+                let debug_options = self.interpreter.debug_options() ^ option;
+                self.interpreter.set_debug_options(debug_options);
+                let names = debug_options
+                    .iter_names()
+                    .map(|(name, _)| name.to_lowercase())
+                    .collect::<Vec<_>>();
+                match &names[..] {
+                    [] => writeln!(writer, "debug: off")?,
+                    names => writeln!(writer, "debug: {}", names.join(", "))?,
+                }
+            }
+            (command, _) => writeln!(writer, "error: unknown command '{command}', try :help")?,
+        }
+
+        Ok(ReplFlow::Continue)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(repl: &mut Repl, input: &str) -> String {
+        let mut output = vec![];
+        repl.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn repl() -> Repl {
+        Repl::new(
+            EvalDebugOption::NONE,
+            EvalLimits::default(),
+            false,
+            RenderStyle::Plain,
+        )
+    }
+
+    #[test]
+    fn test_repl_continuation() {
+        let output = run(&mut repl(), "a {}\nb {}\nb {\na\n}\n");
+        assert_eq!(output, "> > > . . > \n");
+    }
+
+    #[test]
+    fn test_repl_cancel() {
+        let mut repl = repl();
+        let output = run(&mut repl, "a { ( }\nb {}\n");
+        assert!(output.contains("error: Mismatched brackets"), "{output}");
+        assert!(output.starts_with("> error: ") && output.ends_with("> > \n"));
+
+        let output = run(&mut repl, "b {\n\nc {\n:cancel\nb {}\n");
+        assert_eq!(output, "> . > . > > \n");
+    }
+
+    #[test]
+    fn test_repl_errors() {
+        let mut repl = repl();
+        let output = run(&mut repl, "b {}\nb { c }\n");
+        assert!(output.contains("error: identifiers not found: c\n --> <repl>:1:3\n"));
+
+        let output = run(&mut repl, ":bogus\n:debug all\n");
+        assert!(output.contains("error: unknown command ':bogus', try :help\n"));
+        assert!(output.contains("error: unknown debug option 'all'\n"));
+
+        let output = run(&mut repl, "a {}\n:quit\nb { c }\n");
+        assert_eq!(output, "> > ");
+    }
+
+    #[test]
+    fn test_repl_reset() {
+        let mut repl = repl();
+        let output = run(&mut repl, "a {}\nb {}\nb { a }\n");
+        assert!(!output.contains("error"));
+
+        let output = run(&mut repl, ":reset\nb {}\nb { a }\n");
+        assert!(output.contains("error: identifiers not found: a\n"));
+    }
+
+    #[test]
+    fn test_repl_load() {
+        let dir = std::env::temp_dir().join(format!("deck repl {}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("load me.deck");
+        std::fs::write(&path, "a {}\n").unwrap();
+
+        let mut repl = repl();
+        let output = run(
+            &mut repl,
+            &format!(":load {}\nb {{}}\nb {{ a }}\n", path.display()),
+        );
+        assert!(!output.contains("error"), "{output}");
+
+        let output = run(&mut repl, ":load missing file.deck\n");
+        assert!(output.contains("error: cannot read missing file.deck: "));
+
+        // The session goes on after stdin is rejected
+        let output = run(&mut repl, ":load -\nc {}\n");
+        assert_eq!(
+            output,
+            "> error: cannot load <stdin>, it is the session input\n> > \n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+use std::process::ExitCode;
+
+use super::{ColorChoice, RunArgs, SrcInput};
+use deck::unstable::check;
+use deck::{Interner, Interpreter};
+
+/// Run every source file in order.
+///
+/// All files are checked before any of them is evaluated. The files share a
+/// definition stack, so a file can use the definitions of the files before it.
+pub fn run(args: &RunArgs, color: ColorChoice) -> ExitCode {
+    let debug_options = args.debug.options();
+    let limits = args.limits.limits();
+    let style = color.style(&std::io::stderr());
+
+    if SrcInput::is_stdin_repeated(&args.files) {
+        eprintln!("error: {} is given more than once", SrcInput::Stdin);
+        return ExitCode::FAILURE;
+    }
+
+    let mut programs = vec![];
+    let mut has_errors = false;
+    for input in &args.files {
+        let src = match input.read() {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: cannot read {input}: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        // The interpreter checks again when loading, with its own interner
+        let result = check(&src, &mut Interner::new());
+        if !result.diagnostics.is_empty() {
+            eprint!(
+                "{}",
+                result.diagnostics.render(&input.to_string(), &src, style)
+            );
+        }
+        has_errors |= result.diagnostics.has_errors();
+        programs.push((input, src));
+    }
+
+    if has_errors {
+        return ExitCode::FAILURE;
+    }
+
+    let mut interpreter = Interpreter::new_with_debug(debug_options);
+    interpreter.set_limits(limits);
+    if args.extensions.numeric {
+        interpreter.register_numeric();
+    }
+    for (input, src) in programs {
+        if let Err(err) = interpreter.load_source(input.to_string(), src) {
+            eprint!("{}", err.render(style));
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
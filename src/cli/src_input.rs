@@ -0,0 +1,64 @@
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Source input given on the command line.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum SrcInput {
+    /// Standard input, given as `-`.
+    Stdin,
+
+    /// A file path.
+    File(PathBuf),
+}
+
+impl SrcInput {
+    /// Read the whole source.
+    pub fn read(&self) -> std::io::Result<String> {
+        match self {
+            SrcInput::Stdin => {
+                let mut src = String::new();
+                std::io::stdin().read_to_string(&mut src)?;
+                Ok(src)
+            }
+            SrcInput::File(path) => std::fs::read_to_string(path),
+        }
+    }
+
+    /// Check if stdin is given more than once, it can only be read once.
+    pub fn is_stdin_repeated(inputs: &[SrcInput]) -> bool {
+        inputs.iter().filter(|x| **x == SrcInput::Stdin).count() > 1
+    }
+}
+
+impl From<&str> for SrcInput {
+    fn from(value: &str) -> Self {
+        match value {
+            "-" => SrcInput::Stdin,
+            path => SrcInput::File(PathBuf::from(path)),
+        }
+    }
+}
+
+impl std::fmt::Display for SrcInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SrcInput::Stdin => f.write_str("<stdin>"),
+            SrcInput::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_src_input_is_stdin_repeated() {
+        let inputs = |args: &[&str]| args.iter().map(|x| SrcInput::from(*x)).collect::<Vec<_>>();
+        assert!(!SrcInput::is_stdin_repeated(&inputs(&[
+            "a.deck", "-", "b.deck"
+        ])));
+        assert!(!SrcInput::is_stdin_repeated(&inputs(&["a.deck", "a.deck"])));
+        assert!(SrcInput::is_stdin_repeated(&inputs(&["-", "a.deck", "-"])));
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::{EvalEvent, EvalIdentsKind, Interner};
+
+/// Breakpoint parsing error.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, thiserror::Error)]
+pub enum BreakpointError {
+    /// The breakpoint is empty.
+    #[error("expected a line number or a signature")]
+    Empty,
+
+    /// The line number is zero.
+    #[error("line numbers start at 1")]
+    LineZero,
+
+    /// The signature has parameters only, and would match everything.
+    #[error("a signature must have at least one non-parameter identifier")]
+    ParamOnlySignature,
+}
+
+/// Debugger breakpoint.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Breakpoint {
+    /// Break before evaluating a node on a source line.
+    Line(usize),
+
+    /// Break when expressions matching a signature are resolved.
+    ///
+    /// Words starting with `$` are parameters: `mul_2_add_3 $x`.
+    /// They are kept as text, since breakpoints are parsed before evaluation.
+    Signature(Vec<String>),
+}
+
+impl Breakpoint {
+    /// Check if an event hits the breakpoint, resolving its identifiers
+    /// through `interner`.
+    pub fn hits(&self, event: &EvalEvent, interner: &Interner) -> bool {
+        match (self, event) {
+            (Breakpoint::Line(line), EvalEvent::Step { node }) => node.span().start.line == *line,
+            (Breakpoint::Signature(signature), EvalEvent::Resolved { idents, .. }) => {
+                // Matches like `EvalIdentsExtensions::matches` on a parsed signature
+                let mut params = HashSet::new();
+                signature.len() == idents.len()
+                    && signature.iter().zip(idents.iter()).all(|(word, ident)| {
+                        match (word.starts_with('$'), ident) {
+                            (_, EvalIdentsKind::Param(_)) => false,
+                            (true, _) => params.insert(word),
+                            (false, EvalIdentsKind::Expr(ident)) => {
+                                interner.resolve(*ident) == word
+                            }
+                            (false, EvalIdentsKind::Int(n)) => n.to_string() == *word,
+                            (false, _) => false,
+                        }
+                    })
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = BreakpointError;
+
+    /// Parse a line number, or a signature.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(line) = s.trim().parse::<usize>() {
+            return match line {
+                0 => Err(BreakpointError::LineZero),
+                line => Ok(Breakpoint::Line(line)),
+            };
+        }
+
+        let signature = s.split_whitespace().map(String::from).collect::<Vec<_>>();
+        if signature.is_empty() {
+            return Err(BreakpointError::Empty);
+        }
+        if signature.iter().all(|word| word.starts_with('$')) {
+            return Err(BreakpointError::ParamOnlySignature);
+        }
+
+        Ok(Breakpoint::Signature(signature))
+    }
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Line(line) => write!(f, "line {line}"),
+            Breakpoint::Signature(signature) => f.write_str(&signature.join(" ")),
+        }
+    }
+}
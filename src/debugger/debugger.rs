@@ -0,0 +1,330 @@
+use super::*;
+use crate::{EvalEvent, EvalFlow, EvalObserver, EvalOutput, EvalStack};
+
+/// Command returned by a debugger frontend while paused.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum DebugCommand {
+    /// Pause at the next stop.
+    StepIn,
+
+    /// Pause at the next stop in the same or an outer scope.
+    StepOver,
+
+    /// Pause at the next stop in an outer scope.
+    StepOut,
+
+    /// Run until a breakpoint.
+    Continue,
+
+    /// Add a breakpoint, and stay paused.
+    Break(Breakpoint),
+
+    /// Remove the breakpoint at an index, and stay paused.
+    Delete(usize),
+
+    /// Stop evaluation with [`crate::EvalError::Aborted`].
+    Quit,
+}
+
+/// Reason of a pause.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum DebugPauseReason {
+    /// A step command finished.
+    Step,
+
+    /// The breakpoint at an index was hit.
+    Breakpoint(usize),
+}
+
+/// Debugger pause, given to the frontend.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DebugPause<'p> {
+    pub event: &'p EvalEvent<'p>,
+    pub reason: DebugPauseReason,
+
+    /// Number of bodies being evaluated.
+    pub depth: usize,
+    pub breakpoints: &'p [Breakpoint],
+}
+
+/// Debugger frontend, deciding what to do when paused.
+pub trait DebugFrontend: std::fmt::Debug {
+    /// Handle a pause, and return the next command.
+    ///
+    /// Called again after commands that stay paused.
+    fn pause(
+        &mut self,
+        pause: &DebugPause,
+        stack: &EvalStack,
+        output: &mut dyn EvalOutput,
+    ) -> DebugCommand;
+}
+
+/// Debugger stepping mode.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum DebugMode {
+    StepIn,
+    StepOver(usize),
+    StepOut(usize),
+    Continue,
+}
+
+/// Step debugger.
+///
+/// Stops before each node is evaluated and at each resolution of the
+/// reductions in between, and pauses there when stepping or on a breakpoint.
+/// Starts paused at the first stop.
+#[derive(Debug)]
+pub struct Debugger<F> {
+    frontend: F,
+    breakpoints: Vec<Breakpoint>,
+    mode: DebugMode,
+    depth: usize,
+}
+
+impl<F> Debugger<F>
+where
+    F: DebugFrontend,
+{
+    /// Create a new debugger.
+    pub fn new(frontend: F) -> Self {
+        Self {
+            frontend,
+            breakpoints: vec![],
+            mode: DebugMode::StepIn,
+            depth: 0,
+        }
+    }
+
+    /// Add a breakpoint.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    /// Pause at the next stop, or run until a breakpoint.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.mode = match paused {
+            true => DebugMode::StepIn,
+            false => DebugMode::Continue,
+        };
+    }
+
+    /// Get the breakpoints.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Handle a stop, pausing if needed.
+    fn stop(
+        &mut self,
+        event: &EvalEvent,
+        stack: &EvalStack,
+        output: &mut dyn EvalOutput,
+    ) -> EvalFlow {
+        let stepped = match self.mode {
+            DebugMode::StepIn => true,
+            DebugMode::StepOver(depth) => self.depth <= depth,
+            DebugMode::StepOut(depth) => self.depth < depth,
+            DebugMode::Continue => false,
+        };
+        if !stepped
+            && !self
+                .breakpoints
+                .iter()
+                .any(|x| x.hits(event, stack.interner()))
+        {
+            return EvalFlow::Continue;
+        }
+
+        loop {
+            // Breakpoints can change while paused
+            let reason = match self
+                .breakpoints
+                .iter()
+                .position(|x| x.hits(event, stack.interner()))
+            {
+                Some(idx) => DebugPauseReason::Breakpoint(idx),
+                None => DebugPauseReason::Step,
+            };
+            let pause = DebugPause {
+                event,
+                reason,
+                depth: self.depth,
+                breakpoints: &self.breakpoints,
+            };
+            self.mode = match self.frontend.pause(&pause, stack, output) {
+                DebugCommand::StepIn => DebugMode::StepIn,
+                DebugCommand::StepOver => DebugMode::StepOver(self.depth),
+                DebugCommand::StepOut => DebugMode::StepOut(self.depth),
+                DebugCommand::Continue => DebugMode::Continue,
+                DebugCommand::Break(breakpoint) => {
+                    self.breakpoints.push(breakpoint);
+                    continue;
+                }
+                DebugCommand::Delete(idx) => {
+                    if idx < self.breakpoints.len() {
+                        self.breakpoints.remove(idx);
+                    }
+                    continue;
+                }
+                DebugCommand::Quit => return EvalFlow::Abort,
+            };
+            return EvalFlow::Continue;
+        }
+    }
+}
+
+impl<F> EvalObserver for Debugger<F>
+where
+    F: DebugFrontend,
+{
+    fn event(
+        &mut self,
+        event: &EvalEvent,
+        stack: &EvalStack,
+        output: &mut dyn EvalOutput,
+    ) -> EvalFlow {
+        match event {
+            EvalEvent::ScopePushed { .. } => self.depth += 1,
+            EvalEvent::ScopePopped { .. } => self.depth = self.depth.saturating_sub(1),
+            EvalEvent::Step { .. } | EvalEvent::Resolved { .. } => {
+                return self.stop(event, stack, output)
+            }
+            _ => {}
+        }
+        EvalFlow::Continue
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::Interpreter;
+
+    /// Frontend running scripted commands and recording pauses.
+    #[derive(Debug, Clone, Default)]
+    struct Script {
+        commands: Arc<Mutex<VecDeque<DebugCommand>>>,
+        pauses: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Script {
+        fn new(commands: impl IntoIterator<Item = DebugCommand>) -> Self {
+            Self {
+                commands: Arc::new(Mutex::new(commands.into_iter().collect())),
+                pauses: Default::default(),
+            }
+        }
+    }
+
+    impl DebugFrontend for Script {
+        fn pause(
+            &mut self,
+            pause: &DebugPause,
+            _: &EvalStack,
+            _: &mut dyn EvalOutput,
+        ) -> DebugCommand {
+            let pos = &pause.event.span().start;
+            let reason = match pause.reason {
+                DebugPauseReason::Step => "step".to_string(),
+                DebugPauseReason::Breakpoint(idx) => format!("break {idx}"),
+            };
+            self.pauses.lock().unwrap().push(format!(
+                "{reason}: {} {}:{} depth {}",
+                pause.event.name(),
+                pos.line,
+                pos.col,
+                pause.depth,
+            ));
+            self.commands
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(DebugCommand::Continue)
+        }
+    }
+
+    const SRC: &str =
+        "1 {}\nf {}\nf $x {\n    y {}\n    y { $x }\n    y\n}\nz {}\nz { f 1 }\nw {}\nw { f z }";
+
+    fn debug(commands: impl IntoIterator<Item = DebugCommand>) -> Vec<String> {
+        let script = Script::new(commands);
+        let mut interpreter = Interpreter::new();
+        interpreter.add_observer(Debugger::new(script.clone()));
+        interpreter.load_source("test", SRC).unwrap();
+        let pauses = script.pauses.lock().unwrap().clone();
+        pauses
+    }
+
+    #[test]
+    fn test_debugger_breakpoints() {
+        assert_eq!(
+            debug([
+                DebugCommand::Break("f $x".parse().unwrap()),
+                DebugCommand::Break(Breakpoint::Line(5)),
+                DebugCommand::Continue,
+                DebugCommand::Continue,
+                DebugCommand::Delete(0),
+                DebugCommand::Continue,
+            ]),
+            [
+                "step: step 1:3 depth 0",
+                "step: step 1:3 depth 0",
+                "step: step 1:3 depth 0",
+                "break 0: resolved 9:3 depth 0",
+                "break 1: step 5:7 depth 1",
+                "break 0: step 5:7 depth 1",
+                "break 0: step 5:7 depth 1",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_debugger_steps() {
+        assert_eq!(
+            debug([
+                DebugCommand::Break(Breakpoint::Line(9)),
+                DebugCommand::Continue,
+                DebugCommand::StepIn,
+                DebugCommand::StepIn,
+                DebugCommand::StepOver,
+                DebugCommand::StepIn,
+                DebugCommand::StepOut,
+                DebugCommand::StepOver,
+            ]),
+            [
+                "step: step 1:3 depth 0",
+                "step: step 1:3 depth 0",
+                "break 0: step 9:3 depth 0",
+                "step: resolved 9:3 depth 0",
+                "step: step 4:7 depth 1",
+                "step: step 5:7 depth 1",
+                "step: resolved 5:7 depth 1",
+                "step: resolved 9:1 depth 0",
+                "step: step 10:3 depth 0",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_debugger_quit() {
+        let script = Script::new([DebugCommand::StepIn, DebugCommand::Quit]);
+        let mut interpreter = Interpreter::new();
+        interpreter.add_observer(Debugger::new(script.clone()));
+        let err = interpreter.load_source("test", SRC).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                crate::InterpreterError::Eval {
+                    error: crate::EvalError::Aborted { .. },
+                    ..
+                }
+            ),
+            "{err:?}",
+        );
+        assert_eq!(script.pauses.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,5 @@
+mod breakpoint;
+pub use breakpoint::*;
+#[allow(clippy::module_inception)]
+mod debugger;
+pub use debugger::*;
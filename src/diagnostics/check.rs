@@ -0,0 +1,128 @@
+use super::*;
+use crate::{
+    Interner, SemErrorKind, SemNode, SemNodeExpr, SemParser, SrcCodeIterExt, SrcId, SynParser,
+    Token,
+};
+
+/// Result of checking source code.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CheckResult {
+    /// Source the spans point into.
+    pub id: SrcId,
+    pub nodes: Vec<SemNode>,
+    pub diagnostics: Diagnostics,
+
+    /// Comment tokens of the source, in order.
+    pub comments: Vec<Token>,
+}
+
+/// Parse source code and collect every syntactic and semantic error.
+///
+/// Identifiers are interned into `interner`. Nothing is evaluated, the nodes
+/// can be evaluated with the same interner if there are no errors.
+pub fn check(src: &str, interner: &mut Interner) -> CheckResult {
+    check_with_id(src, SrcId::default(), interner)
+}
+
+/// Check source code whose spans point into the source `id`.
+pub fn check_with_id(src: &str, id: SrcId, interner: &mut Interner) -> CheckResult {
+    let mut diagnostics = Diagnostics::new();
+
+    let (comments, tokens): (Vec<_>, Vec<_>) = src
+        .char_indices()
+        .src_code()
+        .with_id(id)
+        .lexer(interner)
+        .filter(|x| !x.value.is_spaces() && !x.value.is_newlines())
+        .partition(|x| x.value.is_comment());
+    let syn_nodes = SynParser::new(tokens).parse();
+    syn_nodes.collect_diagnostics(&mut diagnostics);
+
+    let nodes = SemParser::new(syn_nodes).collect::<Vec<_>>();
+    nodes.collect_diagnostics(&mut diagnostics);
+
+    diagnostics.sort();
+    CheckResult {
+        id,
+        nodes,
+        diagnostics,
+        comments,
+    }
+}
+
+/// Result of checking expressions.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CheckExprsResult {
+    pub exprs: Vec<SemNodeExpr>,
+    pub diagnostics: Diagnostics,
+}
+
+/// Parse source code made of expressions only, and collect every error.
+pub fn check_exprs(src: &str, interner: &mut Interner) -> CheckExprsResult {
+    check_exprs_with_id(src, SrcId::default(), interner)
+}
+
+/// Check expressions whose spans point into the source `id`.
+pub fn check_exprs_with_id(src: &str, id: SrcId, interner: &mut Interner) -> CheckExprsResult {
+    let mut diagnostics = Diagnostics::new();
+
+    let syn_nodes = src
+        .char_indices()
+        .src_code()
+        .with_id(id)
+        .lexer(interner)
+        .parse_syn()
+        .parse();
+    syn_nodes.collect_diagnostics(&mut diagnostics);
+
+    let exprs = SemParser::new(syn_nodes.clone())
+        .parse_expr_vec()
+        .expect("expressions always parse");
+    if let Some(node) = syn_nodes.get(exprs.advance) {
+        diagnostics.push(
+            Diagnostic::error(SemErrorKind::UnexpectedBody.to_string(), node.span.clone())
+                .with_label("expected expressions only"),
+        );
+    }
+    exprs.item.collect_diagnostics(&mut diagnostics);
+
+    diagnostics.sort();
+    CheckExprsResult {
+        exprs: exprs.item,
+        diagnostics,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_collects_all_errors() {
+        let result = check(
+            "1 {}\na { b ( }\nc ) d {}\n( x {} ) e {}\nf g\n}",
+            &mut Interner::new(),
+        );
+        assert_eq!(
+            result
+                .diagnostics
+                .iter()
+                .map(|x| (x.msg.as_str(), x.primary.span.start.line))
+                .collect::<Vec<_>>(),
+            [
+                ("Mismatched brackets: '(' and '}'", 2),
+                ("Mismatched brackets: '{' and ')'", 3),
+                ("Unexpected definition body in expression", 4),
+                ("Expected a definition body after expressions", 5),
+                ("Unexpected closing bracket '}'", 6),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_check_no_errors() {
+        let result = check("1 {}\n2 {}\n2 { 1 }\ndbg! { 2 }", &mut Interner::new());
+        assert!(result.diagnostics.is_empty());
+        assert_eq!(result.nodes.len(), 4);
+    }
+}
@@ -0,0 +1,73 @@
+use super::*;
+use crate::{
+    SemErrorKind, SemNode, SemNodeExpr, SemNodeExprKind, SemNodeKind, SynNode, SynNodeKind,
+};
+
+/// Trait for collecting diagnostics from a tree of nodes.
+pub trait CollectDiagnostics {
+    /// Add the diagnostics of this node and its children.
+    fn collect_diagnostics(&self, diagnostics: &mut Diagnostics);
+}
+
+impl<T> CollectDiagnostics for [T]
+where
+    T: CollectDiagnostics,
+{
+    fn collect_diagnostics(&self, diagnostics: &mut Diagnostics) {
+        self.iter().for_each(|x| x.collect_diagnostics(diagnostics));
+    }
+}
+
+impl CollectDiagnostics for SynNode {
+    fn collect_diagnostics(&self, diagnostics: &mut Diagnostics) {
+        match &self.value {
+            SynNodeKind::Ident(_) | SynNodeKind::Str(_) => {}
+            SynNodeKind::Brac { children, .. } => children.collect_diagnostics(diagnostics),
+            SynNodeKind::Error { children, .. } => {
+                diagnostics.extend(self.to_diagnostic());
+                children.collect_diagnostics(diagnostics);
+            }
+        }
+    }
+}
+
+/// Syntactic errors wrapped in semantic nodes are skipped, they are
+/// collected from [`SynNode`].
+impl CollectDiagnostics for SemNode {
+    fn collect_diagnostics(&self, diagnostics: &mut Diagnostics) {
+        match &self.value {
+            SemNodeKind::Def {
+                idents,
+                body,
+                exprs,
+            } => {
+                idents.collect_diagnostics(diagnostics);
+                body.collect_diagnostics(diagnostics);
+                exprs.collect_diagnostics(diagnostics);
+            }
+            SemNodeKind::Error { kind, children } => {
+                if !matches!(kind, SemErrorKind::Syn(_)) {
+                    diagnostics.extend(self.to_diagnostic());
+                }
+                children.collect_diagnostics(diagnostics);
+            }
+        }
+    }
+}
+
+/// Syntactic errors wrapped in semantic nodes are skipped, they are
+/// collected from [`SynNode`].
+impl CollectDiagnostics for SemNodeExpr {
+    fn collect_diagnostics(&self, diagnostics: &mut Diagnostics) {
+        match &self.value {
+            SemNodeExprKind::Ident(_) | SemNodeExprKind::Str(_) => {}
+            SemNodeExprKind::Inner(inner) => inner.collect_diagnostics(diagnostics),
+            SemNodeExprKind::Error { kind, children } => {
+                if !matches!(kind, SemErrorKind::Syn(_)) {
+                    diagnostics.extend(self.to_diagnostic());
+                }
+                children.collect_diagnostics(diagnostics);
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::Span;
+
+/// Diagnostic severity.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// Label pointing at a span of source code.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Label {
+    pub span: Span,
+    pub msg: String,
+}
+
+impl Label {
+    /// Create a new label.
+    pub fn new(span: Span, msg: impl Into<String>) -> Self {
+        Self {
+            span,
+            msg: msg.into(),
+        }
+    }
+}
+
+/// Diagnostic message about source code.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub msg: String,
+    pub primary: Label,
+    pub secondary: Vec<Label>,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic.
+    pub fn new(severity: Severity, msg: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity,
+            code: None,
+            msg: msg.into(),
+            primary: Label::new(span, ""),
+            secondary: vec![],
+        }
+    }
+
+    /// Create a new error diagnostic.
+    pub fn error(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Error, msg, span)
+    }
+
+    /// Create a new warning diagnostic.
+    pub fn warning(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Warning, msg, span)
+    }
+
+    /// Set the code, such as the name of a lint.
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Set the message of the primary label.
+    pub fn with_label(mut self, msg: impl Into<String>) -> Self {
+        self.primary.msg = msg.into();
+        self
+    }
+
+    /// Add a secondary label.
+    pub fn with_secondary(mut self, span: Span, msg: impl Into<String>) -> Self {
+        self.secondary.push(Label::new(span, msg));
+        self
+    }
+}
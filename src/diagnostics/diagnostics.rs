@@ -0,0 +1,100 @@
+use super::*;
+use crate::SrcId;
+
+/// Collection of diagnostics.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a diagnostic.
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.items.push(diagnostic);
+    }
+
+    /// Check if there are no diagnostics.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Get the number of diagnostics.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Check if any diagnostic is an error.
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(|x| x.severity == Severity::Error)
+    }
+
+    /// Iterate over the diagnostics.
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.items.iter()
+    }
+
+    /// Sort the diagnostics by source, then by where they start in it.
+    pub fn sort(&mut self) {
+        self.items
+            .sort_by_key(|x| (x.primary.span.src, x.primary.span.start.idx));
+    }
+
+    /// Render all diagnostics, separated by empty lines.
+    pub fn render(&self, name: &str, src: &str, style: RenderStyle) -> String {
+        self.items
+            .iter()
+            .map(|x| x.render(name, src, style))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render all diagnostics with the sources their spans point into.
+    pub fn render_with<'a>(
+        &self,
+        source: impl Fn(SrcId) -> Option<(&'a str, &'a str)>,
+        style: RenderStyle,
+    ) -> String {
+        self.items
+            .iter()
+            .map(|x| x.render_with(&source, style))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Extend<Diagnostic> for Diagnostics {
+    fn extend<T: IntoIterator<Item = Diagnostic>>(&mut self, iter: T) {
+        self.items.extend(iter);
+    }
+}
+
+impl FromIterator<Diagnostic> for Diagnostics {
+    fn from_iter<T: IntoIterator<Item = Diagnostic>>(iter: T) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
@@ -0,0 +1,15 @@
+mod diagnostic;
+pub use diagnostic::*;
+#[allow(clippy::module_inception)]
+mod diagnostics;
+pub use diagnostics::*;
+mod render;
+pub use render::*;
+mod sources;
+pub use sources::*;
+mod to_diagnostic;
+pub use to_diagnostic::*;
+mod collect;
+pub use collect::*;
+mod check;
+pub use check::*;
@@ -0,0 +1,238 @@
+use super::*;
+use crate::SrcId;
+
+/// Diagnostic render style.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum RenderStyle {
+    /// Plain text.
+    Plain,
+
+    /// Text colored with ANSI escape codes.
+    Ansi,
+}
+
+/// ANSI escape codes used by a render style.
+struct Palette {
+    error: &'static str,
+    warning: &'static str,
+    accent: &'static str,
+    bold: &'static str,
+    reset: &'static str,
+}
+
+impl RenderStyle {
+    fn palette(self) -> Palette {
+        match self {
+            RenderStyle::Plain => Palette {
+                error: "",
+                warning: "",
+                accent: "",
+                bold: "",
+                reset: "",
+            },
+            RenderStyle::Ansi => Palette {
+                error: "\x1b[1;31m",
+                warning: "\x1b[1;33m",
+                accent: "\x1b[1;34m",
+                bold: "\x1b[1m",
+                reset: "\x1b[0m",
+            },
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Render the diagnostic with the source code its spans point into.
+    ///
+    /// `name` is the name of the source shown next to the location.
+    pub fn render(&self, name: &str, src: &str, style: RenderStyle) -> String {
+        self.render_with(|_| Some((name, src)), style)
+    }
+
+    /// Render the diagnostic with the sources its spans point into.
+    ///
+    /// `source` gets the name and code of a source by its identifier,
+    /// labels in sources that are not found are skipped.
+    pub fn render_with<'a>(
+        &self,
+        source: impl Fn(SrcId) -> Option<(&'a str, &'a str)>,
+        style: RenderStyle,
+    ) -> String {
+        let palette = style.palette();
+        let severity = match self.severity {
+            Severity::Error => palette.error,
+            Severity::Warning => palette.warning,
+        };
+
+        // Labels of the primary source come first, spans of another source may
+        // point past the end of the one they are rendered with
+        let primary_src = self.primary.span.src;
+        let mut labels = std::iter::once((&self.primary, severity, '^'))
+            .chain(self.secondary.iter().map(|x| (x, palette.accent, '-')))
+            .filter_map(|(label, color, mark)| {
+                let (name, src) = source(label.span.src)?;
+                (label.span.start.idx <= src.len()).then_some((label, name, src, color, mark))
+            })
+            .collect::<Vec<_>>();
+        labels.sort_by_key(|(label, ..)| {
+            let span = &label.span;
+            (span.src != primary_src, span.src, span.start.line)
+        });
+
+        let width = labels
+            .iter()
+            .map(|(label, ..)| label.span.start.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(width);
+        let Palette {
+            accent,
+            bold,
+            reset,
+            ..
+        } = palette;
+
+        let start = &self.primary.span.start;
+        let code = match &self.code {
+            Some(code) => format!("[{code}]"),
+            None => String::new(),
+        };
+        let mut out = format!(
+            "{severity}{}{code}{reset}{bold}: {}{reset}\n",
+            self.severity, self.msg,
+        );
+        if let Some((name, _)) = source(primary_src) {
+            out.push_str(&format!(
+                "{pad}{accent}-->{reset} {name}:{}:{}\n",
+                start.line, start.col,
+            ));
+        }
+        out.push_str(&format!("{pad} {accent}|{reset}\n"));
+
+        let mut prev_line = None;
+        let mut prev_src = primary_src;
+        for (label, name, src, color, mark) in labels {
+            let line = label.span.start.line;
+            let idx = floor_char_boundary(src, label.span.start.idx);
+            let (line_start, line_end) = line_bounds(src, idx);
+
+            if prev_src != label.span.src {
+                out.push_str(&format!(
+                    "{pad}{accent}:::{reset} {name}:{line}:{}\n{pad} {accent}|{reset}\n",
+                    label.span.start.col,
+                ));
+                prev_line = None;
+                prev_src = label.span.src;
+            }
+
+            if prev_line != Some(line) {
+                if prev_line.is_some_and(|prev| prev + 1 < line) {
+                    out.push_str(&format!("{accent}...{reset}\n"));
+                }
+                out.push_str(&format!(
+                    "{accent}{line:>width$} |{reset} {}\n",
+                    src[line_start..line_end].trim_end_matches('\r'),
+                ));
+                prev_line = Some(line);
+            }
+
+            let end = floor_char_boundary(src, label.span.end_idx().clamp(idx, line_end));
+            let indent = src[line_start..idx]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let marks = mark
+                .to_string()
+                .repeat(src[idx..end].chars().count().max(1));
+            let msg = match label.msg.as_str() {
+                "" => String::new(),
+                msg => format!(" {msg}"),
+            };
+            out.push_str(&format!(
+                "{pad} {accent}|{reset} {indent}{color}{marks}{msg}{reset}\n"
+            ));
+        }
+
+        out
+    }
+}
+
+/// Get the largest char boundary of `src` that is not after `idx`.
+fn floor_char_boundary(src: &str, idx: usize) -> usize {
+    let mut idx = idx.min(src.len());
+    while !src.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+/// Get the byte range of the line containing `idx`, without the newline.
+///
+/// `idx` must be a char boundary of `src`.
+fn line_bounds(src: &str, idx: usize) -> (usize, usize) {
+    let start = src[..idx].rfind('\n').map_or(0, |i| i + 1);
+    let end = src[idx..].find('\n').map_or(src.len(), |i| idx + i);
+    (start, end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Interner, Span, SpanPos, SrcCodeIterExt, SynNodeKind};
+
+    #[test]
+    fn test_render_mismatched_brac() {
+        let src = "a {\n    ( b }\n}";
+        let nodes = src
+            .char_indices()
+            .src_code()
+            .lexer(&mut Interner::new())
+            .parse_syn()
+            .parse();
+        let SynNodeKind::Brac { children, .. } = &nodes[1].value else {
+            panic!("expected brackets: {nodes:?}");
+        };
+        let diagnostic = children[0].to_diagnostic().expect("error node");
+
+        assert_eq!(
+            diagnostic.render("test.deck", src, RenderStyle::Plain),
+            "\
+error: Mismatched brackets: '(' and '}'
+ --> test.deck:2:9
+  |
+2 |     ( b }
+  |         ^ expected ')'
+  |     - opened here
+",
+        );
+    }
+
+    #[test]
+    fn test_render_foreign_span() {
+        // Spans of another source can split a char or point past the end
+        let src = "éé {\n}";
+        let span = |idx, len| {
+            Span::new(
+                SpanPos {
+                    line: 1,
+                    col: 2,
+                    idx,
+                },
+                len,
+            )
+        };
+        let diagnostic =
+            Diagnostic::error("foreign", span(1, 2)).with_secondary(span(100, 1), "gone");
+
+        assert_eq!(
+            diagnostic.render("test.deck", src, RenderStyle::Plain),
+            "\
+error: foreign
+ --> test.deck:1:2
+  |
+1 | éé {
+  | ^
+",
+        );
+    }
+}
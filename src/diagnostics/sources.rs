@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use crate::SrcId;
+
+/// Named sources that spans point into.
+///
+/// Identifiers start at 1, so spans of a lone source are never found.
+/// Clones share the sources until one of them changes.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Sources {
+    items: Arc<Vec<(String, Arc<str>)>>,
+}
+
+impl Sources {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source and get its identifier.
+    pub fn push(&mut self, name: impl Into<String>, src: impl Into<Arc<str>>) -> SrcId {
+        Arc::make_mut(&mut self.items).push((name.into(), src.into()));
+        SrcId(self.items.len())
+    }
+
+    /// Remove the last added source.
+    pub fn pop(&mut self) {
+        Arc::make_mut(&mut self.items).pop();
+    }
+
+    /// Get the name and code of a source.
+    pub fn get(&self, id: SrcId) -> Option<(&str, &str)> {
+        let (name, src) = self.items.get(id.0.checked_sub(1)?)?;
+        Some((name, src))
+    }
+
+    /// Get the number of sources.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Check if there are no sources.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
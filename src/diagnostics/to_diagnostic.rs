@@ -0,0 +1,148 @@
+use super::*;
+use crate::{
+    EvalError, EvalIdentsError, Interner, SemErrorKind, SemNode, SemNodeExpr, SemNodeExprKind,
+    SemNodeKind, SimpleDisplay, Span, SynErrorKind, SynNode, SynNodeKind, TokenErrorKind,
+};
+
+/// Trait for values that can be reported as a diagnostic.
+pub trait ToDiagnostic {
+    /// Create a diagnostic, or `None` if there is nothing to report.
+    fn to_diagnostic(&self) -> Option<Diagnostic>;
+}
+
+impl ToDiagnostic for SynNode {
+    fn to_diagnostic(&self) -> Option<Diagnostic> {
+        match &self.value {
+            SynNodeKind::Error { kind, .. } => kind.to_diagnostic(),
+            _ => None,
+        }
+    }
+}
+
+impl ToDiagnostic for SynErrorKind {
+    fn to_diagnostic(&self) -> Option<Diagnostic> {
+        match self {
+            SynErrorKind::MismatchedBrac { open, close } => {
+                let expected = match open.value {
+                    '(' => ')',
+                    _ => '}',
+                };
+                Some(
+                    Diagnostic::error(self.to_string(), close.span.clone())
+                        .with_label(format!("expected '{expected}'"))
+                        .with_secondary(open.span.clone(), "opened here"),
+                )
+            }
+            SynErrorKind::UnclosedBrac { open } => Some(
+                Diagnostic::error(self.to_string(), open.span.clone())
+                    .with_label("this bracket is never closed"),
+            ),
+            SynErrorKind::UnexpectedCloseBrac { close } => Some(
+                Diagnostic::error(self.to_string(), close.span.clone())
+                    .with_label("no bracket to close"),
+            ),
+            SynErrorKind::TooDeep { open } => Some(
+                Diagnostic::error(self.to_string(), open.span.clone())
+                    .with_label("this bracket is nested too deep"),
+            ),
+            SynErrorKind::Token(kind) => kind.to_diagnostic(),
+        }
+    }
+}
+
+impl ToDiagnostic for TokenErrorKind {
+    fn to_diagnostic(&self) -> Option<Diagnostic> {
+        match self {
+            TokenErrorKind::UnterminatedStr { open } => Some(
+                Diagnostic::error(self.to_string(), open.span.clone())
+                    .with_label("this string is never closed on its line"),
+            ),
+            TokenErrorKind::InvalidEscape { escape } => Some(
+                Diagnostic::error(self.to_string(), escape.span.clone())
+                    .with_label("expected one of \\\" \\\\ \\n \\r \\t \\0 \\u{..}"),
+            ),
+        }
+    }
+}
+
+impl ToDiagnostic for SemNode {
+    fn to_diagnostic(&self) -> Option<Diagnostic> {
+        match &self.value {
+            SemNodeKind::Error { kind, .. } => sem_error_diagnostic(kind, &self.span),
+            _ => None,
+        }
+    }
+}
+
+impl ToDiagnostic for SemNodeExpr {
+    fn to_diagnostic(&self) -> Option<Diagnostic> {
+        match &self.value {
+            SemNodeExprKind::Error { kind, .. } => sem_error_diagnostic(kind, &self.span),
+            _ => None,
+        }
+    }
+}
+
+/// Create a diagnostic for a semantic error node at `span`.
+fn sem_error_diagnostic(kind: &SemErrorKind, span: &Span) -> Option<Diagnostic> {
+    match kind {
+        SemErrorKind::Syn(kind) => kind.to_diagnostic(),
+        SemErrorKind::MissingBody => Some(
+            Diagnostic::error(kind.to_string(), span.clone())
+                .with_label("expected '{ ... }' after these expressions"),
+        ),
+        SemErrorKind::UnexpectedBody => Some(
+            Diagnostic::error(kind.to_string(), span.clone())
+                .with_label("definitions are not allowed in '( ... )'"),
+        ),
+    }
+}
+
+impl EvalError {
+    /// Create a diagnostic, naming identifiers through `interner`.
+    pub fn diagnostic(&self, interner: &Interner) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.message(interner), self.span().clone());
+        match self {
+            EvalError::IdentsNotFound { .. } => {
+                diagnostic.with_label("no definition matches these expressions")
+            }
+            EvalError::ArgNotFound { .. } => {
+                diagnostic.with_label("no definition matches this argument")
+            }
+            EvalError::Idents {
+                error: EvalIdentsError::DuplicateParam(_),
+                ..
+            } => diagnostic.with_label("in this definition"),
+            EvalError::Idents { .. } => diagnostic,
+            EvalError::BodyWithoutExprs { .. } => {
+                diagnostic.with_label("expected expressions after the body")
+            }
+            EvalError::ParamOnlySignature { .. } => {
+                diagnostic.with_label("every identifier in the signature is a parameter")
+            }
+            EvalError::Syntax { .. } => diagnostic,
+            EvalError::Native { .. } => diagnostic.with_label("in this native call"),
+            EvalError::CyclicDefinition { leading, cycle, .. } => {
+                // Each identifier refers to the next one, the last one back to the cycle start
+                let chain = leading.iter().chain(cycle);
+                chain.clone().zip(chain.skip(1).chain(cycle.first())).fold(
+                    diagnostic.with_label("these expressions never finish evaluating"),
+                    |diagnostic, ((idents, span), (next, _))| {
+                        diagnostic.with_secondary(
+                            span.clone(),
+                            format!(
+                                "'{}' refers to '{}'",
+                                idents.simple_display(interner),
+                                next.simple_display(interner)
+                            ),
+                        )
+                    },
+                )
+            }
+            EvalError::LimitExceeded { .. } => {
+                diagnostic.with_label("while evaluating this definition")
+            }
+            EvalError::Aborted { .. } => diagnostic.with_label("stopped here"),
+        }
+    }
+}
@@ -1,9 +1,20 @@
+use std::sync::Arc;
+
 use crate::parsers::SemNodeExpr;
-use crate::{EvalIdents, SemNode};
+use crate::{EvalIdents, EvalNativeFn, Interner, SemNode, SimpleDisplay};
+
+/// Body and expressions of a definition.
+///
+/// The nodes are parsed source code, only the evaluator looks into them.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct EvalBody {
+    pub(crate) body: Arc<[SemNode]>,
+    pub(crate) exprs: Arc<[SemNodeExpr]>,
+}
 
 /// Definition value.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
-pub enum EvalDefValue<'a> {
+pub enum EvalDefValue {
     /// Base: a base value
     Base,
 
@@ -13,9 +24,22 @@ pub enum EvalDefValue<'a> {
     /// Expanded: an expanded definition
     Expanded(EvalIdents),
 
-    /// Node: a semantic node
-    Node {
-        body: &'a Vec<SemNode>,
-        exprs: &'a Vec<SemNodeExpr>,
-    },
+    /// Node: a definition body, evaluated when called
+    Node(EvalBody),
+
+    /// Native: a function defined by the host
+    Native(EvalNativeFn),
+}
+
+impl SimpleDisplay for EvalDefValue {
+    fn simple_display(&self, interner: &Interner) -> String {
+        match self {
+            EvalDefValue::Base => String::from("{}"),
+            EvalDefValue::Ref(idents) | EvalDefValue::Expanded(idents) => {
+                idents.simple_display(interner)
+            }
+            EvalDefValue::Node(_) => String::from("{ ... }"),
+            EvalDefValue::Native(_) => String::from("<native>"),
+        }
+    }
 }
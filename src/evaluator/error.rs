@@ -0,0 +1,261 @@
+use crate::{EvalIdents, EvalLimit, Interner, SimpleDisplay, Span, Symbol};
+
+/// Evaluation identifiers error.
+///
+/// Symbols only resolve through an interner, so the messages name them with
+/// [`EvalIdentsError::message`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash, thiserror::Error)]
+pub enum EvalIdentsError {
+    /// A parameter appears more than once in a signature.
+    #[error("parameter already exist")]
+    DuplicateParam(Symbol),
+
+    /// A parameter has no argument to be replaced with.
+    #[error("argument not found for parameter")]
+    ArgNotFound(Symbol),
+
+    /// A parameter is found where only arguments are allowed.
+    #[error("unexpected parameter in arguments")]
+    ParamInArgs(Symbol),
+}
+
+/// Evaluation error.
+///
+/// Symbols only resolve through an interner, so the messages name the
+/// identifiers with [`EvalError::message`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash, thiserror::Error)]
+pub enum EvalError {
+    /// Expressions do not match any definition.
+    #[error("identifiers not found")]
+    IdentsNotFound { idents: EvalIdents, span: Span },
+
+    /// An argument does not evaluate to any definition.
+    #[error("argument not found")]
+    ArgNotFound { idents: EvalIdents, span: Span },
+
+    /// Identifiers cannot be matched or assigned.
+    #[error("{error}")]
+    Idents { error: EvalIdentsError, span: Span },
+
+    /// A definition has a body but no expressions.
+    #[error("definition with a body must have expressions")]
+    BodyWithoutExprs { span: Span },
+
+    /// A definition signature has parameters only.
+    #[error("a definition must have at least one non-parameter identifier")]
+    ParamOnlySignature { span: Span },
+
+    /// An error node from parsing.
+    #[error("{msg}")]
+    Syntax { msg: String, span: Span },
+
+    /// An error returned by a native function.
+    #[error("{msg}")]
+    Native { msg: String, span: Span },
+
+    /// Definitions refer to each other without making progress.
+    ///
+    /// `cycle` holds the identifiers resolved in the cycle and the span of the
+    /// definition each of them resolves to, `leading` holds the ones resolved
+    /// before reaching it.
+    #[error("cyclic definition")]
+    CyclicDefinition {
+        leading: Vec<(EvalIdents, Span)>,
+        cycle: Vec<(EvalIdents, Span)>,
+        span: Span,
+    },
+
+    /// An evaluation limit is exceeded, `span` is the definition being evaluated.
+    #[error("{limit} limit of {max} exceeded")]
+    LimitExceeded {
+        limit: EvalLimit,
+        max: usize,
+        span: Span,
+    },
+
+    /// An observer stopped evaluation, `span` is where it stopped.
+    #[error("evaluation aborted")]
+    Aborted { span: Span },
+}
+
+impl EvalIdentsError {
+    /// Create the message of the error, with the parameter resolved through `interner`.
+    pub fn message(&self, interner: &Interner) -> String {
+        match self {
+            EvalIdentsError::DuplicateParam(param)
+            | EvalIdentsError::ArgNotFound(param)
+            | EvalIdentsError::ParamInArgs(param) => {
+                format!("{self}: {}", interner.resolve(*param))
+            }
+        }
+    }
+}
+
+impl EvalError {
+    /// Create the message of the error, with the identifiers resolved through `interner`.
+    pub fn message(&self, interner: &Interner) -> String {
+        match self {
+            EvalError::IdentsNotFound { idents, .. } | EvalError::ArgNotFound { idents, .. } => {
+                format!("{self}: {}", idents.simple_display(interner))
+            }
+            EvalError::Idents { error, .. } => error.message(interner),
+            EvalError::CyclicDefinition { leading, cycle, .. } => {
+                format!("{self}: {}", display_cycle(leading, cycle, interner))
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// Get the span where the error occurred.
+    pub fn span(&self) -> &Span {
+        match self {
+            EvalError::IdentsNotFound { span, .. }
+            | EvalError::ArgNotFound { span, .. }
+            | EvalError::Idents { span, .. }
+            | EvalError::BodyWithoutExprs { span }
+            | EvalError::ParamOnlySignature { span }
+            | EvalError::Syntax { span, .. }
+            | EvalError::Native { span, .. }
+            | EvalError::CyclicDefinition { span, .. }
+            | EvalError::LimitExceeded { span, .. }
+            | EvalError::Aborted { span } => span,
+        }
+    }
+}
+
+/// Display a cycle and the identifiers leading to it as `a -> b -> c -> b`.
+fn display_cycle(
+    leading: &[(EvalIdents, Span)],
+    cycle: &[(EvalIdents, Span)],
+    interner: &Interner,
+) -> String {
+    leading
+        .iter()
+        .chain(cycle)
+        .chain(cycle.first())
+        .map(|(idents, _)| idents.simple_display(interner))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{check, EvalLimits, Evaluator};
+
+    /// Evaluate a source until the first error, with the line and text of its span.
+    fn eval_err(src: &str, evaluator: impl FnOnce(&mut Evaluator)) -> (EvalError, (usize, &str)) {
+        let mut interner = Interner::new();
+        let nodes = check(src, &mut interner).nodes;
+        let mut e = Evaluator::new(nodes, interner);
+        evaluator(&mut e);
+        let err = e.find_map(Result::err).expect("evaluation fails");
+        let span = err.span().clone();
+        (err, (span.start.line, &src[span.start.idx..span.end_idx()]))
+    }
+
+    #[test]
+    fn test_eval_error_idents_not_found() {
+        let (err, span) = eval_err("a {}\na { b }", |_| {});
+        assert!(matches!(err, EvalError::IdentsNotFound { .. }));
+        assert_eq!(span, (2, "{ b }"));
+    }
+
+    #[test]
+    fn test_eval_error_arg_not_found() {
+        let src = "1 {}\n+ {}\n$a + $b {}\nx {}\nx { 1 + y }";
+        let (err, span) = eval_err(src, |_| {});
+        assert!(matches!(err, EvalError::ArgNotFound { .. }));
+        assert_eq!(span, (5, "{ 1 + y }"));
+    }
+
+    #[test]
+    fn test_eval_error_idents() {
+        let (err, span) = eval_err("f {}\nf $x $x {}", |_| {});
+        assert!(matches!(
+            err,
+            EvalError::Idents {
+                error: EvalIdentsError::DuplicateParam(_),
+                ..
+            }
+        ));
+        assert_eq!(span, (2, "{}"));
+    }
+
+    #[test]
+    fn test_eval_error_body_without_exprs() {
+        let (err, span) = eval_err("f {}\nf $x { g {} }", |_| {});
+        assert!(matches!(err, EvalError::BodyWithoutExprs { .. }));
+        assert_eq!(span, (2, "{ g {} }"));
+    }
+
+    #[test]
+    fn test_eval_error_param_only_signature() {
+        let (err, span) = eval_err("x y {}", |_| {});
+        assert!(matches!(err, EvalError::ParamOnlySignature { .. }));
+        assert_eq!(span, (1, "{}"));
+    }
+
+    #[test]
+    fn test_eval_error_syntax() {
+        let (err, span) = eval_err("a {}\nb { ( }", |_| {});
+        assert!(matches!(err, EvalError::Syntax { .. }));
+        assert_eq!(span, (2, "{ ( }"));
+    }
+
+    #[test]
+    fn test_eval_error_native() {
+        let (err, span) = eval_err("x {}\nx { 1 / 0 }", |e| e.register_numeric());
+        assert!(matches!(err, EvalError::Native { msg, .. } if msg == "division by zero"));
+        assert_eq!(span, (2, "{ 1 / 0 }"));
+    }
+
+    #[test]
+    fn test_eval_error_cyclic_definition() {
+        let (err, span) = eval_err("a {}\nb {}\na { b }\nb { a }\nc {}\nc { a }", |_| {});
+        let EvalError::CyclicDefinition { leading, cycle, .. } = err else {
+            panic!("expected a cyclic definition: {err:?}");
+        };
+        let lines = |x: &[(EvalIdents, Span)]| x.iter().map(|(_, span)| span.start.line).collect();
+        assert_eq!((lines(&leading), lines(&cycle)), (vec![3], vec![4]));
+        assert_eq!(span, (6, "{ a }"));
+    }
+
+    #[test]
+    fn test_eval_error_limit_exceeded() {
+        let limits = EvalLimits {
+            steps: Some(2),
+            ..EvalLimits::UNLIMITED
+        };
+        let (err, span) = eval_err("a {}\nb {}\nb { a }\nc {}\nc { b }", |e| {
+            e.set_limits(limits)
+        });
+        assert!(matches!(
+            err,
+            EvalError::LimitExceeded {
+                limit: EvalLimit::Steps,
+                max: 2,
+                ..
+            }
+        ));
+        assert_eq!(span, (3, "{ a }"));
+    }
+
+    #[test]
+    fn test_eval_error_message() {
+        for (src, msg) in [
+            ("a {}\na { b }", "identifiers not found: b"),
+            ("f {}\nf $x $x {}", "parameter already exist: $x"),
+            (
+                "a {}\nb {}\na { b }\nb { a }\nc {}\nc { a }",
+                "cyclic definition: a -> b -> b",
+            ),
+        ] {
+            let mut interner = Interner::new();
+            let nodes = check(src, &mut interner).nodes;
+            let mut e = Evaluator::new(nodes, interner);
+            let err = e.find_map(Result::err).expect("evaluation fails");
+            assert_eq!(err.message(e.interner()), msg);
+        }
+    }
+}
@@ -1,18 +1,12 @@
 use crate::{
-    AdvanceIterExt, EvalDefValue, EvalIdents, EvalIdentsExtensions, EvalIdentsKind, EvalStack,
-    EvalStackResolveResult, SemNode, SemNodeExpr, SemNodeExprKind, SemNodeKind, SimpleDisplay,
+    quote_str, EvalBody, EvalDebugObserver, EvalDefValue, EvalError, EvalEvent, EvalFlow,
+    EvalIdents, EvalIdentsExtensions, EvalIdentsKind, EvalLimit, EvalLimits, EvalNativeArgs,
+    EvalNativeFn, EvalObserver, EvalOutput, EvalStack, EvalStackResolveResult, EvalStdout,
+    EvalStepNode, Interner, SemNode, SemNodeExpr, SemNodeExprKind, SemNodeKind, SimpleDisplay,
+    Span, Symbol, EVAL_DBG,
 };
 use std::collections::HashMap;
-
-/// Semantic node iterator traits.
-pub trait AdvanceSemNodeIterator<'a>:
-    Iterator<Item = &'a SemNode> + std::fmt::Debug + AdvanceIterExt
-{
-}
-impl<'a, T: Iterator<Item = &'a SemNode> + std::fmt::Debug + AdvanceIterExt>
-    AdvanceSemNodeIterator<'a> for T
-{
-}
+use std::sync::Arc;
 
 /// Evaluate identifiers option.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -33,197 +27,483 @@ bitflags::bitflags! {
     }
 }
 
+/// Emit an event to the debug observer and the observers.
+///
+/// The event is only built if something observes it.
+macro_rules! emit {
+    ($self:ident, $event:expr) => {
+        let debug = !$self.debug.options().is_empty();
+        if debug || !$self.observers.is_empty() {
+            let event = $event;
+            if debug {
+                $self
+                    .debug
+                    .event(&event, &$self.stack, $self.output.as_mut());
+            }
+            for observer in $self.observers.iter_mut() {
+                if observer.event(&event, &$self.stack, $self.output.as_mut()) == EvalFlow::Abort {
+                    $self.aborted = true;
+                }
+            }
+        }
+    };
+}
+
 /// Evaluator
 #[derive(Debug)]
-pub struct Evaluator<'a> {
-    stack: EvalStack<'a>,
-    debug_options: EvalDebugOption,
+pub struct Evaluator {
+    stack: EvalStack,
+    debug: EvalDebugObserver,
+    output: Box<dyn EvalOutput + Send>,
+    observers: Vec<Box<dyn EvalObserver + Send>>,
+    limits: EvalLimits,
+    steps: usize,
+    depth: usize,
+
+    /// Whether an observer aborted evaluation since the last check.
+    aborted: bool,
 }
 
-impl<'a> Evaluator<'a> {
-    /// Create a new evaluator.
-    pub fn new<Iter>(iter: Iter) -> Self
-    where
-        Iter: AdvanceSemNodeIterator<'a> + 'a,
-    {
+impl Evaluator {
+    /// Create a new evaluator, with the interner the symbols of `nodes` come from.
+    pub(crate) fn new(nodes: impl Into<Arc<[SemNode]>>, interner: Interner) -> Self {
         Self {
-            stack: EvalStack::new(iter),
-            debug_options: EvalDebugOption::NONE,
+            stack: EvalStack::with_nodes(nodes, interner),
+            debug: EvalDebugObserver::new(EvalDebugOption::NONE),
+            output: Box::new(EvalStdout),
+            observers: vec![],
+            limits: EvalLimits::default(),
+            steps: 0,
+            depth: 0,
+            aborted: false,
         }
     }
 
     /// Create a new evaluator with debug options.
-    pub fn new_with_debug<Iter>(iter: Iter, debug_options: EvalDebugOption) -> Self
-    where
-        Iter: AdvanceSemNodeIterator<'a> + 'a,
-    {
+    pub(crate) fn new_with_debug(
+        nodes: impl Into<Arc<[SemNode]>>,
+        debug_options: EvalDebugOption,
+        interner: Interner,
+    ) -> Self {
         Self {
-            stack: EvalStack::new(iter),
-            debug_options,
+            stack: EvalStack::with_nodes(nodes, interner),
+            debug: EvalDebugObserver::new(debug_options),
+            output: Box::new(EvalStdout),
+            observers: vec![],
+            limits: EvalLimits::default(),
+            steps: 0,
+            depth: 0,
+            aborted: false,
+        }
+    }
+
+    /// Load more nodes to evaluate against the current definitions.
+    pub(crate) fn load(&mut self, nodes: impl Into<Arc<[SemNode]>>) {
+        self.stack.load(nodes);
+    }
+
+    /// Get the definition stack.
+    pub fn stack(&self) -> &EvalStack {
+        &self.stack
+    }
+
+    /// Get the interner the symbols of the evaluator resolve through.
+    pub fn interner(&self) -> &Interner {
+        self.stack.interner()
+    }
+
+    /// Get the interner mutably, to intern more symbols.
+    pub fn interner_mut(&mut self) -> &mut Interner {
+        self.stack.interner_mut()
+    }
+
+    /// Register a native function, callable from every scope.
+    pub fn register_native(&mut self, key: EvalIdents, f: EvalNativeFn) -> Result<(), EvalError> {
+        self.stack.natives_mut().register(key, f)
+    }
+
+    /// Register the numeric extension, see [`crate::EvalNatives::register_numeric`].
+    pub fn register_numeric(&mut self) {
+        self.stack.register_numeric();
+    }
+
+    /// Get the output sink.
+    pub fn output(&mut self) -> &mut dyn EvalOutput {
+        self.output.as_mut()
+    }
+
+    /// Set the output sink, stdout by default.
+    pub fn set_output(&mut self, output: impl EvalOutput + Send + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// Add an observer of evaluation events.
+    pub fn add_observer(&mut self, observer: impl EvalObserver + Send + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Get the debug options.
+    pub fn debug_options(&self) -> EvalDebugOption {
+        self.debug.options()
+    }
+
+    /// Set the debug options.
+    pub fn set_debug_options(&mut self, debug_options: EvalDebugOption) {
+        self.debug = EvalDebugObserver::new(debug_options);
+    }
+
+    /// Get the evaluation limits.
+    pub fn limits(&self) -> EvalLimits {
+        self.limits
+    }
+
+    /// Set the evaluation limits.
+    pub fn set_limits(&mut self, limits: EvalLimits) {
+        self.limits = limits;
+    }
+
+    /// Reset the number of reduction steps counted against the step limit.
+    pub fn reset_steps(&mut self) {
+        self.steps = 0;
+    }
+
+    /// Check that a count is within a limit.
+    ///
+    /// `span` is the definition being evaluated.
+    fn check_limit(&self, limit: EvalLimit, count: usize, span: &Span) -> Result<(), EvalError> {
+        match self.limits.max(limit) {
+            Some(max) if count > max => Err(EvalError::LimitExceeded {
+                limit,
+                max,
+                span: span.clone(),
+            }),
+            _ => Ok(()),
         }
     }
 
     /// Evaluate identifiers.
-    pub fn eval_idents(
+    pub(crate) fn eval_idents(
         &mut self,
-        exprs: &'a Vec<SemNodeExpr>,
+        exprs: &[SemNodeExpr],
         ident_option: EvalIdentsIdentOption,
-    ) -> EvalIdents {
+    ) -> Result<EvalIdents, EvalError> {
         let mut result = vec![];
 
         for expr in exprs {
             match expr {
                 SemNodeExpr {
                     value: SemNodeExprKind::Ident(ident),
-                    ..
+                    span,
                 } => match ident_option {
                     EvalIdentsIdentOption::ResolveWithStack => {
-                        let idents = vec![EvalIdentsKind::Expr(ident.clone())];
-
-                        fn unwrap_idents(mut idents: Vec<EvalIdentsKind>) -> String {
-                            match idents.pop().unwrap() {
-                                EvalIdentsKind::Expr(ident) => ident,
-                                _ => unreachable!(),
-                            }
-                        }
-
-                        match self.eval_exprs(&idents, false) {
-                            Some(EvalDefValue::Ref(_)) => {
-                                result.push(EvalIdentsKind::Expr(unwrap_idents(idents)))
-                            }
-                            None => result.push(EvalIdentsKind::Param(unwrap_idents(idents))),
-                            Some(value) => panic!("unexpected definition: {:?}", value), // Likely unreachable
+                        // Any value makes the identifier an expression, including an
+                        // expanded one such as `f` after `f { 1 + 1 }`
+                        let word = EvalIdentsKind::word(*ident, self.stack.interner());
+                        match self.eval_exprs(&vec![word.clone()], span, false)? {
+                            Some(_) => result.push(word),
+                            None => result.push(EvalIdentsKind::Param(*ident)),
                         }
                     }
                     EvalIdentsIdentOption::AlwaysExpr => {
-                        result.push(EvalIdentsKind::Expr(ident.clone()))
+                        result.push(EvalIdentsKind::word(*ident, self.stack.interner()))
                     }
                     EvalIdentsIdentOption::AlwaysParam => {
-                        result.push(EvalIdentsKind::Param(ident.clone()))
+                        result.push(EvalIdentsKind::Param(*ident))
                     }
                 },
+                SemNodeExpr {
+                    value: SemNodeExprKind::Str(value),
+                    ..
+                } => result.push(EvalIdentsKind::Str(value.clone())),
                 SemNodeExpr {
                     value: SemNodeExprKind::Inner(inner),
                     ..
                 } => {
-                    let inner_idents = self.eval_idents(inner, ident_option);
+                    let inner_idents = self.eval_idents(inner, ident_option)?;
                     result.push(EvalIdentsKind::Inner(inner_idents));
                 }
                 SemNodeExpr {
-                    value: SemNodeExprKind::Error { msg, .. },
-                    ..
-                } => panic!("error: {}", msg),
+                    value: SemNodeExprKind::Error { kind, .. },
+                    span,
+                } => {
+                    return Err(EvalError::Syntax {
+                        msg: kind.to_string(),
+                        span: span.clone(),
+                    })
+                }
             }
         }
 
-        result
+        Ok(result)
     }
 
     /// Evaluate expressions.
     ///
+    /// `span` is where the expressions come from, and is used for errors.
+    ///
     /// Returns:
     /// - `Some(EvalDefValue::Base)`: if `idents` is empty
     /// - `Some(EvalDefValue::Ref(idents))`: if the expression is found
     /// - `Some(EvalDefValue::Expanded(idents))`: if the expression is expanded
     /// - `None`: if the expression is not found
-    pub fn eval_exprs(&mut self, idents: &EvalIdents, debug: bool) -> Option<EvalDefValue<'a>> {
+    pub fn eval_exprs(
+        &mut self,
+        idents: &EvalIdents,
+        span: &Span,
+        debug: bool,
+    ) -> Result<Option<EvalDefValue>, EvalError> {
+        // Bodies and arguments are evaluated recursively, the depth is checked on resolution
+        self.depth += 1;
+        let value = self.resolve_exprs(idents, span, debug);
+        self.depth -= 1;
+
+        let value = value?;
+        if let Some(value) = &value {
+            emit!(self, EvalEvent::Returned { value, span });
+        }
+        Ok(value)
+    }
+
+    /// Resolve expressions until they evaluate to a value.
+    fn resolve_exprs(
+        &mut self,
+        idents: &EvalIdents,
+        span: &Span,
+        debug: bool,
+    ) -> Result<Option<EvalDefValue>, EvalError> {
         if idents.is_empty() {
-            return Some(EvalDefValue::Base);
+            return Ok(Some(EvalDefValue::Base));
         }
 
+        self.check_limit(EvalLimit::ExprSize, idents.size(), span)?;
+
+        // Identifiers resolved to a reference, with their definition, and their index in the
+        // chain. The stack does not change while references are followed, and every other
+        // value ends the resolution, so leaving this loop is the only progress and any
+        // revisited identifiers are a cycle.
+        let mut chain: Vec<(EvalIdents, Span)> = vec![];
+        let mut chain_idx: HashMap<EvalIdents, usize> = HashMap::new();
+
         let mut curr = idents;
         if debug {
-            println!("-----------dbg-----------\n{}", curr.simple_display());
+            let msg = format!(
+                "-----------dbg-----------\n{}",
+                curr.simple_display(self.stack.interner())
+            );
+            self.output.dbg(&msg);
         }
 
-        while let Some(EvalStackResolveResult { key, value, args }) = self.stack.resolve(curr) {
+        while let Some(EvalStackResolveResult {
+            key,
+            value,
+            span: def_span,
+            args,
+        }) = self.stack.resolve(curr)?
+        {
+            self.steps += 1;
+            self.check_limit(EvalLimit::Steps, self.steps, def_span)?;
+            self.check_limit(EvalLimit::Depth, self.depth, def_span)?;
+
+            emit!(
+                self,
+                EvalEvent::Resolved {
+                    idents: curr,
+                    key,
+                    args: &args,
+                    span,
+                    def_span,
+                }
+            );
+            if std::mem::take(&mut self.aborted) {
+                return Err(EvalError::Aborted { span: span.clone() });
+            }
+
             match value {
                 EvalDefValue::Base => {
                     return match args.len() {
-                        0 => Some(EvalDefValue::Ref(curr.clone())),
+                        0 => Ok(Some(EvalDefValue::Ref(curr.clone()))),
                         _ => {
                             let key_cloned = key.clone();
+                            let def_span = def_span.clone();
                             let mut eval_args = HashMap::new();
                             for (param, arg) in args.into_iter() {
-                                eval_args.insert(
-                                    param,
-                                    EvalIdentsKind::Inner({
-                                        let arg_idents = match arg {
-                                            EvalIdentsKind::Inner(inner) => inner,
-                                            _ => vec![arg],
-                                        };
-
-                                        match self.eval_exprs(&arg_idents, false) {
-                                            Some(EvalDefValue::Ref(idents)) => idents,
-                                            Some(EvalDefValue::Expanded(idents)) => idents,
-                                            _ => panic!("argument not found: {:?}", arg_idents),
-                                        }
-                                    }),
-                                );
+                                let arg_idents = match arg {
+                                    EvalIdentsKind::Inner(inner) => inner,
+                                    _ => vec![arg],
+                                };
+
+                                let arg_idents = match self.eval_exprs(&arg_idents, span, false)? {
+                                    Some(EvalDefValue::Ref(idents)) => idents,
+                                    Some(EvalDefValue::Expanded(idents)) => idents,
+                                    _ => {
+                                        return Err(EvalError::ArgNotFound {
+                                            idents: arg_idents,
+                                            span: span.clone(),
+                                        })
+                                    }
+                                };
+                                eval_args.insert(param, EvalIdentsKind::Inner(arg_idents));
                             }
-                            let expanded = key_cloned.assign_params(&eval_args);
-                            Some(EvalDefValue::Expanded(expanded))
+                            let expanded =
+                                key_cloned.assign_params(&eval_args).map_err(|error| {
+                                    EvalError::Idents {
+                                        error,
+                                        span: def_span.clone(),
+                                    }
+                                })?;
+                            self.check_limit(EvalLimit::ExprSize, expanded.size(), &def_span)?;
+                            emit!(
+                                self,
+                                EvalEvent::Expanded {
+                                    idents: &expanded,
+                                    span,
+                                }
+                            );
+                            Ok(Some(EvalDefValue::Expanded(expanded)))
                         }
-                    }
+                    };
                 }
                 EvalDefValue::Ref(next) | EvalDefValue::Expanded(next) => {
+                    self.check_limit(EvalLimit::ExprSize, next.size(), def_span)?;
+
+                    // A literal redefined as itself, such as `2 { 1 + 1 }`, is its value
+                    if next == curr && self.stack.natives().is_literal(curr) {
+                        return Ok(Some(EvalDefValue::Ref(curr.clone())));
+                    }
+
+                    // Resolving a reference changes nothing, so revisited identifiers never stop
+                    chain_idx.insert(curr.clone(), chain.len());
+                    chain.push((curr.clone(), def_span.clone()));
+                    if let Some(&idx) = chain_idx.get(next) {
+                        let cycle = chain.split_off(idx);
+                        return Err(EvalError::CyclicDefinition {
+                            leading: chain,
+                            cycle,
+                            span: span.clone(),
+                        });
+                    }
+
                     if debug {
-                        println!("{}", next.simple_display());
+                        self.output.dbg(&next.simple_display(self.stack.interner()));
                     }
+                    emit!(self, EvalEvent::Expanded { idents: next, span });
                     curr = next;
                 }
-                EvalDefValue::Node { body, exprs } => {
-                    let body = *body;
-                    let exprs = *exprs;
-
-                    let iter = body.iter();
-                    self.stack.push_scope(iter.clone());
-
-                    for (param, arg) in args {
-                        self.stack.push_def(
-                            vec![EvalIdentsKind::Expr(param)],
-                            EvalDefValue::Ref(vec![arg]),
-                        );
-                    }
-
-                    self.for_each(|_| ());
-
-                    let exprs_idents = self.eval_idents(exprs, EvalIdentsIdentOption::AlwaysExpr);
-                    let def_value = self
-                        .eval_exprs(&exprs_idents, debug)
-                        .expect(format!("identifiers not found: {exprs_idents:?}").as_str());
-
+                EvalDefValue::Node(EvalBody { body, exprs }) => {
+                    let body = body.clone();
+                    let exprs = exprs.clone();
+                    let def_span = def_span.clone();
+
+                    self.stack.push_scope(body);
+                    emit!(self, EvalEvent::ScopePushed { span });
+                    let def_value = self.eval_node(args, &exprs, span, &def_span, debug);
                     self.stack.pop_scope();
+                    emit!(self, EvalEvent::ScopePopped { span });
+                    let def_value = def_value?;
 
                     if debug {
-                        match &def_value {
-                            EvalDefValue::Ref(idents) | EvalDefValue::Expanded(idents) => {
-                                println!("{}", idents.simple_display())
-                            }
-                            _ => panic!("unexpected definition: {:?}", def_value),
+                        if let EvalDefValue::Ref(idents) | EvalDefValue::Expanded(idents) =
+                            &def_value
+                        {
+                            self.output
+                                .dbg(&idents.simple_display(self.stack.interner()))
                         }
                     }
 
-                    return Some(def_value);
+                    return Ok(Some(def_value));
+                }
+                EvalDefValue::Native(f) => {
+                    let f = f.clone();
+                    let args = EvalNativeArgs {
+                        args,
+                        span: span.clone(),
+                    };
+                    let idents = f.call(self, &args)?;
+                    self.check_limit(EvalLimit::ExprSize, idents.size(), span)?;
+
+                    if debug {
+                        self.output
+                            .dbg(&idents.simple_display(self.stack.interner()));
+                    }
+
+                    return Ok(Some(EvalDefValue::Expanded(idents)));
                 }
             }
         }
 
-        None
+        match self.stack.natives().is_literal(curr) {
+            true => Ok(Some(EvalDefValue::Ref(curr.clone()))),
+            false => Ok(None),
+        }
     }
 
-    /// Evaluate the next node.
-    pub fn step(&mut self, debug_options: EvalDebugOption) -> Option<()> {
-        let node = self.stack.next()?;
+    /// Evaluate the body and expressions of a definition in the current scope.
+    fn eval_node(
+        &mut self,
+        args: HashMap<Symbol, EvalIdentsKind>,
+        exprs: &[SemNodeExpr],
+        span: &Span,
+        def_span: &Span,
+        debug: bool,
+    ) -> Result<EvalDefValue, EvalError> {
+        for (param, arg) in args {
+            self.push_def(
+                vec![EvalIdentsKind::word(param, self.stack.interner())],
+                EvalDefValue::Ref(vec![arg]),
+                span.clone(),
+            )?;
+        }
+
+        for result in self.by_ref() {
+            result?;
+        }
+
+        let exprs_idents = self.eval_idents(exprs, EvalIdentsIdentOption::AlwaysExpr)?;
+        self.eval_exprs(&exprs_idents, def_span, debug)?
+            .ok_or_else(|| EvalError::IdentsNotFound {
+                idents: exprs_idents,
+                span: def_span.clone(),
+            })
+    }
 
-        if debug_options.contains(EvalDebugOption::STACK) {
-            println!("----------stack----------\n{:#?}", self.stack)
-        };
+    /// Push a definition onto the current scope.
+    fn push_def(
+        &mut self,
+        key: EvalIdents,
+        value: EvalDefValue,
+        span: Span,
+    ) -> Result<(), EvalError> {
+        self.stack.push_def(key, value, span)?;
+        emit!(self, {
+            let def = self.stack.last_def().expect("definition is pushed");
+            EvalEvent::DefPushed {
+                key: &def.key,
+                value: &def.value,
+                span: &def.span,
+            }
+        });
+        Ok(())
+    }
 
-        if debug_options.contains(EvalDebugOption::CALL) {
-            println!("----------call-----------\n{}", node.simple_display());
-        };
+    /// Evaluate the next node.
+    pub fn step(&mut self) -> Option<Result<(), EvalError>> {
+        let node = self.stack.next_node()?;
+        emit!(
+            self,
+            EvalEvent::Step {
+                node: EvalStepNode(&node)
+            }
+        );
+        if std::mem::take(&mut self.aborted) {
+            return Some(Err(EvalError::Aborted {
+                span: node.span.clone(),
+            }));
+        }
+        Some(self.eval_node_def(&node))
+    }
 
+    /// Evaluate a definition node.
+    fn eval_node_def(&mut self, node: &SemNode) -> Result<(), EvalError> {
         match node {
             SemNode {
                 value:
@@ -232,31 +512,45 @@ impl<'a> Evaluator<'a> {
                         body,
                         exprs,
                     },
-                ..
+                span,
             } => {
                 if idents.is_empty() {
-                    return Some(());
+                    return Ok(());
                 }
 
+                // `dbg! { exprs }` calls the native `dbg! (exprs)`
                 let dbg = matches!(
                     idents.last().unwrap(),
-                    SemNodeExpr { value: SemNodeExprKind::Ident(y), .. } if y == "dbg!",
+                    SemNodeExpr { value: SemNodeExprKind::Ident(y), .. } if self.stack.interner().resolve(*y) == EVAL_DBG,
                 );
 
                 if !body.is_empty() {
                     if exprs.is_empty() {
-                        panic!("definition with a body must have expressions");
+                        return Err(EvalError::BodyWithoutExprs { span: span.clone() });
                     }
 
                     let def_idents =
-                        self.eval_idents(idents, EvalIdentsIdentOption::ResolveWithStack);
-                    self.stack
-                        .push_def(def_idents, EvalDefValue::Node { body, exprs });
+                        self.eval_idents(idents, EvalIdentsIdentOption::ResolveWithStack)?;
+                    let value = EvalDefValue::Node(EvalBody {
+                        body: body.clone(),
+                        exprs: exprs.clone(),
+                    });
+                    self.push_def(def_idents, value, span.clone())?;
                 } else {
-                    let exprs_idents = self.eval_idents(exprs, EvalIdentsIdentOption::AlwaysExpr);
-                    let def_value = self
-                        .eval_exprs(&exprs_idents, dbg)
-                        .expect(format!("identifiers not found: {exprs_idents:?}").as_str());
+                    let mut exprs_idents =
+                        self.eval_idents(exprs, EvalIdentsIdentOption::AlwaysExpr)?;
+                    if dbg {
+                        exprs_idents = vec![
+                            EvalIdentsKind::Expr(self.stack.interner_mut().intern(EVAL_DBG)),
+                            EvalIdentsKind::Inner(exprs_idents),
+                        ];
+                    }
+                    let def_value =
+                        self.eval_exprs(&exprs_idents, span, false)?
+                            .ok_or_else(|| EvalError::IdentsNotFound {
+                                idents: exprs_idents,
+                                span: span.clone(),
+                            })?;
 
                     if !dbg {
                         let ident_len = idents.len();
@@ -269,40 +563,99 @@ impl<'a> Evaluator<'a> {
                                 },
                                 _ => EvalIdentsIdentOption::ResolveWithStack,
                             },
-                        );
-                        self.stack.push_def(def_idents, def_value);
+                        )?;
+                        self.push_def(def_idents, def_value, span.clone())?;
                     }
                 }
             }
             SemNode {
-                value: SemNodeKind::Error { msg, .. },
-                ..
-            } => panic!("error: {}", msg),
+                value: SemNodeKind::Error { kind, .. },
+                span,
+            } => {
+                return Err(EvalError::Syntax {
+                    msg: kind.to_string(),
+                    span: span.clone(),
+                })
+            }
         }
-        Some(())
+        Ok(())
     }
 }
 
-impl<'a> Iterator for Evaluator<'a> {
-    type Item = ();
+impl Iterator for Evaluator {
+    type Item = Result<(), EvalError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.step(self.debug_options)
+        self.step()
     }
 }
 
+/// Evaluate nodes with a new evaluator, with the interner the symbols of `nodes` come from.
+pub fn evaluate(nodes: impl Into<Arc<[SemNode]>>, interner: Interner) -> Result<(), EvalError> {
+    Evaluator::new(nodes, interner).collect()
+}
+
 /// Evaluation identifiers to debug output trait.
 impl SimpleDisplay for EvalIdents {
-    fn simple_display(&self) -> String {
+    fn simple_display(&self, interner: &Interner) -> String {
         self.iter()
             .map(|ident| match ident {
-                EvalIdentsKind::Expr(ident) => ident.clone(),
+                EvalIdentsKind::Expr(ident) | EvalIdentsKind::Param(ident) => {
+                    interner.resolve(*ident).to_string()
+                }
+                EvalIdentsKind::Str(value) => quote_str(value),
+                EvalIdentsKind::Int(n) => n.to_string(),
                 EvalIdentsKind::Inner(inner) => {
-                    format!("({})", inner.simple_display())
+                    format!("({})", inner.simple_display(interner))
                 }
-                _ => panic!("cannot print parameter: {:?}", ident),
             })
             .collect::<Vec<String>>()
             .join(" ")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::check;
+
+    #[test]
+    fn test_evaluator_load() {
+        let mut interner = Interner::new();
+        let nodes = check("1 {}\n+ {}\n$a + $b {}", &mut interner).nodes;
+        let mut evaluator = Evaluator::new(nodes, interner);
+        evaluator.by_ref().collect::<Result<(), _>>().unwrap();
+
+        // The nodes of each program are dropped once loaded, functions keep their body
+        for src in [
+            "2 {}\n2 { 1 + 1 }",
+            "f {}\nf $x { r {}\nr { $x + 1 } r }",
+            "3 {}\n3 { f 2 }",
+        ] {
+            let nodes = check(src, evaluator.interner_mut()).nodes;
+            evaluator.load(nodes);
+            evaluator.by_ref().collect::<Result<(), _>>().unwrap();
+        }
+
+        let def = evaluator.stack().last_def().unwrap();
+        assert_eq!(def.key.simple_display(evaluator.interner()), "3");
+        assert_eq!(
+            def.value.simple_display(evaluator.interner()),
+            "((1) + (1)) + (1)"
+        );
+    }
+
+    #[test]
+    fn test_evaluator_signature_expr() {
+        // `f` evaluates to an expanded value, which used to panic as an unexpected definition
+        let src = "1 {}\n+ {}\n$a + $b {}\nf {}\nf { 1 + 1 }\nf $x {}";
+        let mut interner = Interner::new();
+        let nodes = check(src, &mut interner).nodes;
+        let mut evaluator = Evaluator::new(nodes, interner);
+        evaluator.by_ref().collect::<Result<(), _>>().unwrap();
+
+        let def = evaluator.stack().last_def().unwrap();
+        assert_eq!(def.key.simple_display(evaluator.interner()), "f $x");
+        assert!(matches!(def.key[1], EvalIdentsKind::Param(_)));
+    }
+}
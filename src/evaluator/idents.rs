@@ -1,70 +1,185 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{EvalIdentsError, Interner, Symbol};
 
 /// Evaluation identifier kind.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum EvalIdentsKind {
-    Expr(String),
-    Param(String),
+    Expr(Symbol),
+    Str(Arc<str>),
+    Int(i64),
+    Param(Symbol),
     Inner(EvalIdents),
 }
 
+impl EvalIdentsKind {
+    /// Create the identifier of a word, with its text resolved through `interner`.
+    ///
+    /// Words written the way an integer prints are [`EvalIdentsKind::Int`],
+    /// so integers computed by natives are never interned.
+    pub fn word(ident: Symbol, interner: &Interner) -> Self {
+        match parse_int_word(interner.resolve(ident)) {
+            Some(n) => EvalIdentsKind::Int(n),
+            None => EvalIdentsKind::Expr(ident),
+        }
+    }
+}
+
 /// Evaluation identifiers.
 pub type EvalIdents = Vec<EvalIdentsKind>;
 
+/// Parse a word written the way an integer prints, so `42` and `-7` are
+/// integers but `+7` and `007` are not.
+fn parse_int_word(word: &str) -> Option<i64> {
+    let n = word.parse::<i64>().ok()?;
+    (n.to_string() == word).then_some(n)
+}
+
+/// Parse a signature written by the host.
+///
+/// Words starting with `$` are parameters: `read-file $path`.
+pub fn parse_signature(signature: &str, interner: &mut Interner) -> EvalIdents {
+    signature
+        .split_whitespace()
+        .map(|word| match word.starts_with('$') {
+            true => EvalIdentsKind::Param(interner.intern(word)),
+            false => match parse_int_word(word) {
+                Some(n) => EvalIdentsKind::Int(n),
+                None => EvalIdentsKind::Expr(interner.intern(word)),
+            },
+        })
+        .collect()
+}
+
 /// Evaluation identifiers extensions trait.
 pub trait EvalIdentsExtensions {
     /// Check if the argument matches this identifier.
     /// The argument must not contain any parameters.
-    fn matches(&self, idents: &EvalIdents) -> Option<HashMap<String, EvalIdentsKind>>;
+    fn matches(
+        &self,
+        idents: &EvalIdents,
+    ) -> Result<Option<HashMap<Symbol, EvalIdentsKind>>, EvalIdentsError>;
 
     /// Assign arguments to parameters.
-    fn assign_params(self, args: &HashMap<String, EvalIdentsKind>) -> EvalIdents;
+    fn assign_params(
+        self,
+        args: &HashMap<Symbol, EvalIdentsKind>,
+    ) -> Result<EvalIdents, EvalIdentsError>;
+
+    /// Find the first parameter that appears more than once.
+    fn duplicate_param(&self) -> Option<Symbol>;
+
+    /// Check if every argument matched by `other` is also matched by this identifier.
+    fn subsumes(&self, other: &EvalIdents) -> bool;
+
+    /// Count the identifiers, including the ones in inner expressions.
+    fn size(&self) -> usize;
 }
 
 impl EvalIdentsExtensions for EvalIdents {
-    fn matches(&self, idents: &EvalIdents) -> Option<HashMap<String, EvalIdentsKind>> {
+    fn matches(
+        &self,
+        idents: &EvalIdents,
+    ) -> Result<Option<HashMap<Symbol, EvalIdentsKind>>, EvalIdentsError> {
         if idents.len() == 0 || self.len() != idents.len() {
-            return None;
+            return Ok(None);
         }
 
         let mut params = HashMap::new();
 
         for (a, b) in self.iter().zip(idents.iter()) {
             match (a, b) {
-                (_, EvalIdentsKind::Param(_)) => {
-                    panic!("idents contain parameters");
+                (_, EvalIdentsKind::Param(b)) => {
+                    return Err(EvalIdentsError::ParamInArgs(*b));
                 }
                 (EvalIdentsKind::Expr(a), EvalIdentsKind::Expr(b)) => {
                     if a != b {
-                        return None;
+                        return Ok(None);
+                    }
+                }
+                (EvalIdentsKind::Str(a), EvalIdentsKind::Str(b)) => {
+                    if a != b {
+                        return Ok(None);
                     }
                 }
-                (EvalIdentsKind::Inner(a), EvalIdentsKind::Inner(b)) => match a.matches(b) {
+                (EvalIdentsKind::Int(a), EvalIdentsKind::Int(b)) => {
+                    if a != b {
+                        return Ok(None);
+                    }
+                }
+                (EvalIdentsKind::Inner(a), EvalIdentsKind::Inner(b)) => match a.matches(b)? {
                     Some(child_params) => params.extend(child_params),
-                    _ => return None,
+                    _ => return Ok(None),
                 },
                 (EvalIdentsKind::Param(a), b) => {
-                    if let Some(old) = params.insert(a.clone(), b.clone()) {
-                        panic!("parameter already exist: {:?}", old);
+                    if params.insert(*a, b.clone()).is_some() {
+                        return Err(EvalIdentsError::DuplicateParam(*a));
                     }
                 }
-                _ => return None,
+                _ => return Ok(None),
             }
         }
 
-        Some(params)
+        Ok(Some(params))
     }
 
-    fn assign_params(self, args: &HashMap<String, EvalIdentsKind>) -> EvalIdents {
+    fn assign_params(
+        self,
+        args: &HashMap<Symbol, EvalIdentsKind>,
+    ) -> Result<EvalIdents, EvalIdentsError> {
         self.into_iter()
             .map(|ident| match ident {
                 EvalIdentsKind::Param(param) => match args.get(&param) {
-                    Some(ident) => ident.clone(),
-                    None => panic!("argument not found for parameter: {:?}", param),
+                    Some(ident) => Ok(ident.clone()),
+                    None => Err(EvalIdentsError::ArgNotFound(param)),
                 },
-                EvalIdentsKind::Inner(inner) => EvalIdentsKind::Inner(inner.assign_params(args)),
-                _ => ident.clone(),
+                EvalIdentsKind::Inner(inner) => {
+                    Ok(EvalIdentsKind::Inner(inner.assign_params(args)?))
+                }
+                _ => Ok(ident),
             })
             .collect()
     }
+
+    fn duplicate_param(&self) -> Option<Symbol> {
+        fn collect(idents: &EvalIdents, params: &mut Vec<Symbol>) -> Option<Symbol> {
+            for ident in idents {
+                match ident {
+                    EvalIdentsKind::Param(param) if params.contains(param) => return Some(*param),
+                    EvalIdentsKind::Param(param) => params.push(*param),
+                    EvalIdentsKind::Inner(inner) => {
+                        if let Some(param) = collect(inner, params) {
+                            return Some(param);
+                        }
+                    }
+                    EvalIdentsKind::Expr(_) | EvalIdentsKind::Str(_) | EvalIdentsKind::Int(_) => {}
+                }
+            }
+            None
+        }
+
+        collect(self, &mut vec![])
+    }
+
+    fn subsumes(&self, other: &EvalIdents) -> bool {
+        self.len() == other.len()
+            && self.iter().zip(other.iter()).all(|(a, b)| match (a, b) {
+                (EvalIdentsKind::Param(_), _) => true,
+                (EvalIdentsKind::Expr(a), EvalIdentsKind::Expr(b)) => a == b,
+                (EvalIdentsKind::Str(a), EvalIdentsKind::Str(b)) => a == b,
+                (EvalIdentsKind::Int(a), EvalIdentsKind::Int(b)) => a == b,
+                (EvalIdentsKind::Inner(a), EvalIdentsKind::Inner(b)) => a.subsumes(b),
+                _ => false,
+            })
+    }
+
+    fn size(&self) -> usize {
+        self.iter()
+            .map(|ident| match ident {
+                EvalIdentsKind::Inner(inner) => 1 + inner.size(),
+                _ => 1,
+            })
+            .sum()
+    }
 }
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::{EvalIdents, EvalIdentsKind};
+
+/// Index of the definitions of a scope.
+///
+/// Definitions are indexed by the length of their key and its first `Expr`
+/// or `Int` identifier, so resolving only matches keys which can match. Keys
+/// without any of them are only indexed by length.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct EvalScopeIndex {
+    /// Definitions by length and position of the first `Expr` or `Int`, then by its value.
+    exprs: HashMap<(usize, usize), HashMap<EvalIdentsKind, Vec<usize>>>,
+
+    /// Positions of the first `Expr` or `Int` of the keys, by length.
+    positions: HashMap<usize, Vec<usize>>,
+
+    /// Definitions without any `Expr` or `Int`, by length.
+    others: HashMap<usize, Vec<usize>>,
+}
+
+impl EvalScopeIndex {
+    /// Create a new empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index the definition at `idx` of the scope.
+    ///
+    /// Definitions must be indexed in order.
+    pub fn insert(&mut self, key: &EvalIdents, idx: usize) {
+        let len = key.len();
+        let first = key.iter().enumerate().find(|(_, ident)| is_indexed(ident));
+
+        match first {
+            Some((pos, expr)) => {
+                let positions = self.positions.entry(len).or_default();
+                if !positions.contains(&pos) {
+                    positions.push(pos);
+                }
+                self.exprs
+                    .entry((len, pos))
+                    .or_default()
+                    .entry(expr.clone())
+                    .or_default()
+                    .push(idx);
+            }
+            None => self.others.entry(len).or_default().push(idx),
+        }
+    }
+
+    /// Get the definitions which can match `idents`, latest first.
+    pub fn candidates(&self, idents: &EvalIdents) -> Vec<usize> {
+        let len = idents.len();
+        let exprs = self
+            .positions
+            .get(&len)
+            .into_iter()
+            .flatten()
+            .filter_map(|pos| match &idents[*pos] {
+                expr if is_indexed(expr) => self.exprs.get(&(len, *pos))?.get(expr),
+                _ => None,
+            });
+
+        let mut candidates = exprs
+            .chain(self.others.get(&len))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        candidates.sort_unstable_by(|a, b| b.cmp(a));
+        candidates
+    }
+}
+
+/// Check if definitions are indexed by an identifier.
+fn is_indexed(ident: &EvalIdentsKind) -> bool {
+    matches!(ident, EvalIdentsKind::Expr(_) | EvalIdentsKind::Int(_))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_signature, Interner};
+
+    #[test]
+    fn test_scope_index_candidates() {
+        let mut interner = Interner::new();
+        let mut index = EvalScopeIndex::new();
+        for (idx, key) in ["1", "+", "$a + $b", "1 + 1", "f $x", "$a - $b", "$a + $b"]
+            .into_iter()
+            .enumerate()
+        {
+            index.insert(&parse_signature(key, &mut interner), idx);
+        }
+        index.insert(
+            &vec![EvalIdentsKind::Inner(parse_signature("x", &mut interner))],
+            7,
+        );
+
+        assert_eq!(
+            index.candidates(&parse_signature("1", &mut interner)),
+            [7, 0]
+        );
+        assert_eq!(
+            index.candidates(&parse_signature("1 + 2", &mut interner)),
+            [6, 3, 2]
+        );
+        assert_eq!(
+            index.candidates(&parse_signature("1 - 2", &mut interner)),
+            [5, 3]
+        );
+        assert_eq!(
+            index.candidates(&parse_signature("f 1", &mut interner)),
+            [4]
+        );
+        assert_eq!(index.candidates(&vec![EvalIdentsKind::Inner(vec![])]), [7]);
+        assert!(index
+            .candidates(&parse_signature("g 1", &mut interner))
+            .is_empty());
+    }
+}
@@ -0,0 +1,58 @@
+use strum_macros::Display;
+
+/// Evaluation limit kind.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Display)]
+pub enum EvalLimit {
+    /// Number of reduction steps.
+    #[strum(serialize = "step")]
+    Steps,
+
+    /// Number of expressions evaluated inside each other, by bodies and arguments.
+    #[strum(serialize = "depth")]
+    Depth,
+
+    /// Number of identifiers in an expression, including inner expressions.
+    #[strum(serialize = "expression size")]
+    ExprSize,
+}
+
+/// Evaluation limits, `None` is unlimited.
+///
+/// Steps are counted from the last call to [`crate::Evaluator::reset_steps`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct EvalLimits {
+    pub steps: Option<usize>,
+    pub depth: Option<usize>,
+    pub expr_size: Option<usize>,
+}
+
+impl EvalLimits {
+    /// Default depth limit, low enough to not overflow a 2 MiB stack.
+    pub const DEFAULT_DEPTH: usize = 128;
+
+    /// No limits at all.
+    pub const UNLIMITED: Self = Self {
+        steps: None,
+        depth: None,
+        expr_size: None,
+    };
+
+    /// Get the maximum of a limit.
+    pub fn max(&self, limit: EvalLimit) -> Option<usize> {
+        match limit {
+            EvalLimit::Steps => self.steps,
+            EvalLimit::Depth => self.depth,
+            EvalLimit::ExprSize => self.expr_size,
+        }
+    }
+}
+
+impl Default for EvalLimits {
+    /// Only the depth is limited.
+    fn default() -> Self {
+        Self {
+            depth: Some(Self::DEFAULT_DEPTH),
+            ..Self::UNLIMITED
+        }
+    }
+}
@@ -1,8 +1,23 @@
 mod idents;
 pub use idents::*;
+#[allow(clippy::module_inception)]
 mod evaluator;
 pub use evaluator::*;
 mod stack;
 pub use stack::*;
+mod index;
+pub use index::*;
 mod def;
 pub use def::*;
+mod error;
+pub use error::*;
+mod native;
+pub use native::*;
+mod numeric;
+pub use numeric::*;
+mod output;
+pub use output::*;
+mod observer;
+pub use observer::*;
+mod limits;
+pub use limits::*;
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    numeric_native, parse_int, parse_signature, EvalDefValue, EvalError, EvalIdents,
+    EvalIdentsError, EvalIdentsExtensions, EvalIdentsKind, EvalStackDef, Evaluator, Interner, Span,
+    Symbol, EVAL_NUMERIC_OPS,
+};
+
+/// Identifier of the builtin debug function.
+pub const EVAL_DBG: &str = "dbg!";
+
+/// Native function callback.
+///
+/// Receives the evaluator and the arguments bound to the parameters of the
+/// signature, and returns the identifiers the call evaluates to.
+pub type EvalNativeCallback =
+    dyn Fn(&mut Evaluator, &EvalNativeArgs) -> Result<EvalIdents, EvalError> + Send + Sync;
+
+/// Native function, defined by the host.
+#[derive(Clone)]
+pub struct EvalNativeFn {
+    f: Arc<EvalNativeCallback>,
+
+    /// Whether the function evaluates its arguments.
+    eager_args: bool,
+}
+
+impl EvalNativeFn {
+    /// Create a new native function, its arguments are not evaluated.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&mut Evaluator, &EvalNativeArgs) -> Result<EvalIdents, EvalError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            f: Arc::new(f),
+            eager_args: false,
+        }
+    }
+
+    /// Declare that the function evaluates its arguments,
+    /// so they must resolve like the arguments of a base definition.
+    pub fn with_eager_args(mut self) -> Self {
+        self.eager_args = true;
+        self
+    }
+
+    /// Check if the function evaluates its arguments.
+    pub fn eager_args(&self) -> bool {
+        self.eager_args
+    }
+
+    /// Call the native function.
+    pub fn call(
+        &self,
+        evaluator: &mut Evaluator,
+        args: &EvalNativeArgs,
+    ) -> Result<EvalIdents, EvalError> {
+        (self.f)(evaluator, args)
+    }
+}
+
+impl std::fmt::Debug for EvalNativeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EvalNativeFn")
+            .field("eager_args", &self.eager_args)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Native functions are equal if they are the same function.
+impl PartialEq for EvalNativeFn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.f, &other.f) && self.eager_args == other.eager_args
+    }
+}
+
+impl Eq for EvalNativeFn {}
+
+impl std::hash::Hash for EvalNativeFn {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.f).cast::<()>().hash(state);
+        self.eager_args.hash(state);
+    }
+}
+
+/// Arguments of a native function call.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EvalNativeArgs {
+    /// Arguments bound to the parameters, not evaluated.
+    pub args: HashMap<Symbol, EvalIdentsKind>,
+
+    /// Span of the expressions that called the function.
+    pub span: Span,
+}
+
+impl EvalNativeArgs {
+    /// Get the identifiers bound to a parameter, the interner is the
+    /// evaluator's: [`Evaluator::interner`].
+    pub fn get(&self, param: &str, interner: &Interner) -> Option<EvalIdents> {
+        match self.args.get(&interner.get(param)?)? {
+            EvalIdentsKind::Inner(inner) => Some(inner.clone()),
+            arg => Some(vec![arg.clone()]),
+        }
+    }
+
+    /// Create an error at the call.
+    pub fn error(&self, msg: impl Into<String>) -> EvalError {
+        EvalError::Native {
+            msg: msg.into(),
+            span: self.span.clone(),
+        }
+    }
+}
+
+/// Native function registry.
+///
+/// Natives are resolved after every scope, so definitions shadow them.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct EvalNatives {
+    defs: Vec<EvalStackDef>,
+    numeric: bool,
+}
+
+impl EvalNatives {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the builtin functions.
+    pub fn with_builtins(interner: &mut Interner) -> Self {
+        let mut natives = Self::new();
+        natives
+            .register(
+                vec![
+                    EvalIdentsKind::Expr(interner.intern(EVAL_DBG)),
+                    EvalIdentsKind::Param(interner.intern("$x")),
+                ],
+                EvalNativeFn::new(native_dbg).with_eager_args(),
+            )
+            .expect("builtin signatures are valid");
+        natives
+    }
+
+    /// Register a native function.
+    ///
+    /// A later function with the same signature shadows the earlier one.
+    pub fn register(&mut self, key: EvalIdents, f: EvalNativeFn) -> Result<(), EvalError> {
+        let span = Span::of_src("");
+
+        if key.iter().all(|x| matches!(x, EvalIdentsKind::Param(_))) {
+            return Err(EvalError::ParamOnlySignature { span });
+        }
+
+        if let Some(param) = key.duplicate_param() {
+            return Err(EvalError::Idents {
+                error: EvalIdentsError::DuplicateParam(param),
+                span,
+            });
+        }
+
+        self.defs.push(EvalStackDef {
+            key,
+            value: EvalDefValue::Native(f),
+            span,
+        });
+
+        Ok(())
+    }
+
+    /// Register the numeric extension.
+    ///
+    /// Integer literals resolve to themselves after every definition, and
+    /// `$a + $b`, `-`, `*`, `/`, `%`, `<` and `=` compute on them.
+    pub fn register_numeric(&mut self, interner: &mut Interner) {
+        if self.numeric {
+            return;
+        }
+
+        for (op, f) in EVAL_NUMERIC_OPS {
+            self.register(
+                parse_signature(&format!("$a {op} $b"), interner),
+                numeric_native(op, f),
+            )
+            .expect("numeric signatures are valid");
+        }
+        self.numeric = true;
+    }
+
+    /// Check if the numeric extension is registered.
+    pub fn numeric(&self) -> bool {
+        self.numeric
+    }
+
+    /// Check if identifiers are a literal: a string, or an integer literal
+    /// of the numeric extension.
+    pub fn is_literal(&self, idents: &EvalIdents) -> bool {
+        match idents.as_slice() {
+            [EvalIdentsKind::Str(_)] => true,
+            _ => self.numeric && parse_int(idents).is_some(),
+        }
+    }
+
+    /// Get the signatures of the registered functions.
+    pub fn keys(&self) -> impl Iterator<Item = &EvalIdents> {
+        self.defs.iter().map(|def| &def.key)
+    }
+
+    /// Get the registered functions as definitions, latest first.
+    pub fn defs(&self) -> impl Iterator<Item = &EvalStackDef> {
+        self.defs.iter().rev()
+    }
+}
+
+/// Evaluate the argument, printing each resolution step.
+fn native_dbg(evaluator: &mut Evaluator, args: &EvalNativeArgs) -> Result<EvalIdents, EvalError> {
+    let idents = args
+        .get("$x", evaluator.interner())
+        .expect("dbg! has a parameter");
+    match evaluator.eval_exprs(&idents, &args.span, true)? {
+        Some(EvalDefValue::Ref(idents) | EvalDefValue::Expanded(idents)) => Ok(idents),
+        Some(_) => Ok(vec![]),
+        None => Err(EvalError::IdentsNotFound {
+            idents,
+            span: args.span.clone(),
+        }),
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{
+    EvalDefValue, EvalError, EvalIdents, EvalIdentsKind, EvalNativeArgs, EvalNativeFn, Evaluator,
+};
+
+/// Numeric operator function, `None` if the result does not fit.
+pub type EvalNumericFn = fn(i64, i64) -> Option<i64>;
+
+/// Operators of the numeric extension, comparisons evaluate to `1` or `0`.
+pub const EVAL_NUMERIC_OPS: [(&str, EvalNumericFn); 7] = [
+    ("+", i64::checked_add),
+    ("-", i64::checked_sub),
+    ("*", i64::checked_mul),
+    ("/", i64::checked_div),
+    ("%", i64::checked_rem),
+    ("<", |a, b| Some((a < b) as i64)),
+    ("=", |a, b| Some((a == b) as i64)),
+];
+
+/// Parse identifiers as an integer literal.
+///
+/// Literals are a single [`EvalIdentsKind::Int`], see [`EvalIdentsKind::word`].
+pub fn parse_int(idents: &EvalIdents) -> Option<i64> {
+    match idents.as_slice() {
+        [EvalIdentsKind::Int(n)] => Some(*n),
+        _ => None,
+    }
+}
+
+/// Create the identifiers of an integer literal.
+pub fn int_idents(n: i64) -> EvalIdents {
+    vec![EvalIdentsKind::Int(n)]
+}
+
+/// Create the native function of a numeric operator.
+///
+/// Operands that do not evaluate to literals stay symbolic, the way a base
+/// definition `$a + $b {}` expands them.
+pub fn numeric_native(op: &'static str, f: EvalNumericFn) -> EvalNativeFn {
+    EvalNativeFn::new(move |evaluator, args| {
+        let a = eval_operand(evaluator, args, "$a")?;
+        let b = eval_operand(evaluator, args, "$b")?;
+        match (parse_int(&a), parse_int(&b)) {
+            (Some(x), Some(y)) => match f(x, y) {
+                Some(n) => Ok(int_idents(n)),
+                None => Err(match (op, y) {
+                    ("/" | "%", 0) => args.error("division by zero"),
+                    _ => args.error(format!("integer overflow in {x} {op} {y}")),
+                }),
+            },
+            _ => Ok(vec![
+                EvalIdentsKind::Inner(a),
+                EvalIdentsKind::Expr(evaluator.interner_mut().intern(op)),
+                EvalIdentsKind::Inner(b),
+            ]),
+        }
+    })
+    .with_eager_args()
+}
+
+/// Evaluate an operand of a numeric operator.
+fn eval_operand(
+    evaluator: &mut Evaluator,
+    args: &EvalNativeArgs,
+    param: &str,
+) -> Result<EvalIdents, EvalError> {
+    let idents = args
+        .get(param, evaluator.interner())
+        .expect("numeric operators have two parameters");
+    match evaluator.eval_exprs(&idents, &args.span, false)? {
+        Some(EvalDefValue::Ref(idents) | EvalDefValue::Expanded(idents)) => Ok(idents),
+        _ => Err(EvalError::ArgNotFound {
+            idents,
+            span: args.span.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_signature, Interner};
+
+    #[test]
+    fn test_parse_int() {
+        let literals = [
+            ("0", Some(0)),
+            ("42", Some(42)),
+            ("-7", Some(-7)),
+            ("+7", None),
+            ("007", None),
+            ("-0", None),
+            ("x", None),
+            ("1 + 1", None),
+        ];
+        let mut interner = Interner::new();
+        for (src, n) in literals {
+            let idents = parse_signature(src, &mut interner);
+            assert_eq!(parse_int(&idents), n, "{src}");
+        }
+        assert_eq!(int_idents(-7), parse_signature("-7", &mut interner));
+
+        // Integers are not interned, wherever they come from
+        assert_eq!(interner.get("42"), None);
+    }
+}
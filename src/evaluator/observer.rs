@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use strum_macros::IntoStaticStr;
+
+use crate::{
+    EvalDebugOption, EvalDefValue, EvalIdents, EvalIdentsKind, EvalOutput, EvalStack, Interner,
+    SemNode, SimpleDisplay, Span, Symbol,
+};
+
+/// Node about to be evaluated, see [`EvalEvent::Step`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EvalStepNode<'e>(pub(crate) &'e SemNode);
+
+impl EvalStepNode<'_> {
+    /// Get the span of the node.
+    pub fn span(&self) -> &Span {
+        &self.0.span
+    }
+}
+
+impl SimpleDisplay for EvalStepNode<'_> {
+    fn simple_display(&self, interner: &Interner) -> String {
+        self.0.simple_display(interner)
+    }
+}
+
+/// Evaluation event.
+///
+/// Events borrow from the evaluator, observers clone what they keep.
+#[derive(Debug, PartialEq, Eq, Clone, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum EvalEvent<'e> {
+    /// A node is about to be evaluated.
+    Step { node: EvalStepNode<'e> },
+
+    /// A definition is pushed onto the current scope.
+    DefPushed {
+        key: &'e EvalIdents,
+        value: &'e EvalDefValue,
+        span: &'e Span,
+    },
+
+    /// A scope is pushed to evaluate a body, `span` is the call.
+    ScopePushed { span: &'e Span },
+
+    /// A scope is popped after evaluating a body, `span` is the call.
+    ScopePopped { span: &'e Span },
+
+    /// Identifiers resolve to a definition.
+    Resolved {
+        idents: &'e EvalIdents,
+        key: &'e EvalIdents,
+        args: &'e HashMap<Symbol, EvalIdentsKind>,
+        span: &'e Span,
+        def_span: &'e Span,
+    },
+
+    /// Identifiers are replaced by the value of their definition.
+    Expanded {
+        idents: &'e EvalIdents,
+        span: &'e Span,
+    },
+
+    /// Expressions evaluate to a value.
+    Returned {
+        value: &'e EvalDefValue,
+        span: &'e Span,
+    },
+}
+
+impl EvalEvent<'_> {
+    /// Get the stable name of the event.
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+
+    /// Get the span of source code the event comes from.
+    pub fn span(&self) -> &Span {
+        match self {
+            EvalEvent::Step { node } => node.span(),
+            EvalEvent::DefPushed { span, .. }
+            | EvalEvent::ScopePushed { span }
+            | EvalEvent::ScopePopped { span }
+            | EvalEvent::Resolved { span, .. }
+            | EvalEvent::Expanded { span, .. }
+            | EvalEvent::Returned { span, .. } => span,
+        }
+    }
+}
+
+/// Whether evaluation goes on after an observer handled an event.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum EvalFlow {
+    Continue,
+
+    /// Stop evaluation with [`crate::EvalError::Aborted`] at the next step or resolution.
+    Abort,
+}
+
+/// Evaluation observer.
+pub trait EvalObserver: std::fmt::Debug {
+    /// Observe an event.
+    ///
+    /// `stack` is the definition stack when the event occurs, and `output`
+    /// the output sink of the evaluator.
+    fn event(
+        &mut self,
+        event: &EvalEvent,
+        stack: &EvalStack,
+        output: &mut dyn EvalOutput,
+    ) -> EvalFlow;
+}
+
+/// Observer writing the traces of [`EvalDebugOption`] to the trace channel.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct EvalDebugObserver {
+    options: EvalDebugOption,
+}
+
+impl EvalDebugObserver {
+    /// Create a new debug observer.
+    pub fn new(options: EvalDebugOption) -> Self {
+        Self { options }
+    }
+
+    /// Get the debug options.
+    pub fn options(&self) -> EvalDebugOption {
+        self.options
+    }
+}
+
+impl EvalObserver for EvalDebugObserver {
+    fn event(
+        &mut self,
+        event: &EvalEvent,
+        stack: &EvalStack,
+        output: &mut dyn EvalOutput,
+    ) -> EvalFlow {
+        let EvalEvent::Step { node } = event else {
+            return EvalFlow::Continue;
+        };
+
+        if self.options.contains(EvalDebugOption::STACK) {
+            output.trace(&format!("----------stack----------\n{stack:#?}"));
+        }
+
+        if self.options.contains(EvalDebugOption::CALL) {
+            output.trace(&format!(
+                "----------call-----------\n{}",
+                node.simple_display(stack.interner())
+            ));
+        }
+
+        EvalFlow::Continue
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::Interpreter;
+
+    /// Observer recording event names and positions.
+    #[derive(Debug, Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl EvalObserver for Recorder {
+        fn event(&mut self, event: &EvalEvent, _: &EvalStack, _: &mut dyn EvalOutput) -> EvalFlow {
+            let pos = &event.span().start;
+            let event = format!("{} {}:{}", event.name(), pos.line, pos.col);
+            self.0.lock().unwrap().push(event);
+            EvalFlow::Continue
+        }
+    }
+
+    #[test]
+    fn test_observer_events() {
+        let mut interpreter = Interpreter::new();
+        let recorder = Recorder::default();
+        interpreter.add_observer(recorder.clone());
+
+        let src = "1 {}\nf {}\nf $x {\n    y {}\n    y { $x }\n    y\n}\nz {}\nz { f 1 }";
+        interpreter.load_source("test", src).unwrap();
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                "step 1:3",
+                "returned 1:3",
+                "def_pushed 1:3",
+                "step 2:3",
+                "returned 2:3",
+                "def_pushed 2:3",
+                "step 3:6",
+                "resolved 3:1",
+                "returned 3:1",
+                "def_pushed 3:6",
+                "step 8:3",
+                "returned 8:3",
+                "def_pushed 8:3",
+                "step 9:3",
+                "resolved 9:3",
+                "scope_pushed 9:3",
+                "def_pushed 9:3",
+                "step 4:7",
+                "returned 4:7",
+                "def_pushed 4:7",
+                "step 5:7",
+                "resolved 5:7",
+                "expanded 5:7",
+                "resolved 5:7",
+                "returned 5:7",
+                "resolved 5:5",
+                "returned 5:5",
+                "def_pushed 5:7",
+                "resolved 3:6",
+                "expanded 3:6",
+                "resolved 3:6",
+                "returned 3:6",
+                "scope_popped 9:3",
+                "returned 9:3",
+                "resolved 9:1",
+                "returned 9:1",
+                "def_pushed 9:3",
+            ],
+        );
+    }
+}
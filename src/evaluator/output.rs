@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Evaluation output sink.
+///
+/// `dbg` receives the output of `dbg!`, and `trace` the output of
+/// [`crate::EvalDebugOption`]. Each call is one message, which can span
+/// several lines.
+pub trait EvalOutput: std::fmt::Debug {
+    /// Write the output of `dbg!`.
+    fn dbg(&mut self, msg: &str);
+
+    /// Write a debug trace.
+    fn trace(&mut self, msg: &str);
+}
+
+/// Output sink writing every channel to stdout.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct EvalStdout;
+
+impl EvalOutput for EvalStdout {
+    fn dbg(&mut self, msg: &str) {
+        println!("{msg}");
+    }
+
+    fn trace(&mut self, msg: &str) {
+        println!("{msg}");
+    }
+}
+
+/// Output sink capturing messages in memory.
+///
+/// Clones share the same messages, so a clone can be given to the evaluator
+/// and the original read afterwards, from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct EvalCapture {
+    messages: Arc<Mutex<EvalCaptureMessages>>,
+}
+
+/// Messages captured by [`EvalCapture`].
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct EvalCaptureMessages {
+    dbg: Vec<String>,
+    trace: Vec<String>,
+}
+
+impl EvalCapture {
+    /// Create a new empty capture.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the captured `dbg!` messages.
+    pub fn dbg(&self) -> Vec<String> {
+        self.messages().dbg.clone()
+    }
+
+    /// Get the captured trace messages.
+    pub fn trace(&self) -> Vec<String> {
+        self.messages().trace.clone()
+    }
+
+    /// Remove every captured message.
+    pub fn clear(&self) {
+        *self.messages() = EvalCaptureMessages::default();
+    }
+
+    /// Lock the messages, a panic while holding them cannot leave them invalid.
+    fn messages(&self) -> MutexGuard<'_, EvalCaptureMessages> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Captures are equal if they captured the same messages.
+impl PartialEq for EvalCapture {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.messages, &other.messages) || *self.messages() == *other.messages()
+    }
+}
+
+impl Eq for EvalCapture {}
+
+impl EvalOutput for EvalCapture {
+    fn dbg(&mut self, msg: &str) {
+        self.messages().dbg.push(msg.to_string());
+    }
+
+    fn trace(&mut self, msg: &str) {
+        self.messages().trace.push(msg.to_string());
+    }
+}
@@ -1,111 +1,242 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::evaluator::AdvanceSemNodeIterator;
 use crate::parsers::SemNode;
-use crate::{EvalDefValue, EvalIdents, EvalIdentsExtensions, EvalIdentsKind};
+use crate::{
+    EvalDefValue, EvalError, EvalIdents, EvalIdentsError, EvalIdentsExtensions, EvalIdentsKind,
+    EvalNatives, EvalScopeIndex, Interner, SimpleDisplay, Span, Symbol,
+};
 
 /// Definition stack resolution result.
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EvalStackResolveResult<'def, 'stack> {
+pub struct EvalStackResolveResult<'stack> {
     pub key: &'stack EvalIdents,
-    pub value: &'stack EvalDefValue<'def>,
-    pub args: HashMap<String, EvalIdentsKind>,
+    pub value: &'stack EvalDefValue,
+    pub span: &'stack Span,
+    pub args: HashMap<Symbol, EvalIdentsKind>,
+}
+
+/// Definition in a scope.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EvalStackDef {
+    pub key: EvalIdents,
+    pub value: EvalDefValue,
+    pub span: Span,
 }
 
 /// Definition stack item.
 #[derive(Debug)]
-pub struct EvalStackItem<'a> {
-    pub scope: Vec<(EvalIdents, EvalDefValue<'a>)>,
-    pub iter: Box<dyn AdvanceSemNodeIterator<'a> + 'a>,
+pub struct EvalStackItem {
+    pub scope: Vec<EvalStackDef>,
+    pub nodes: Arc<[SemNode]>,
+    pub pos: usize,
+    pub index: EvalScopeIndex,
+}
+
+impl EvalStackItem {
+    /// Create a scope evaluating `nodes`.
+    fn new(nodes: Arc<[SemNode]>) -> Self {
+        Self {
+            scope: vec![],
+            nodes,
+            pos: 0,
+            index: EvalScopeIndex::new(),
+        }
+    }
 }
 
 /// Definition stack.
-pub struct EvalStack<'a> {
-    stack: Vec<EvalStackItem<'a>>,
+///
+/// Owns the interner the symbols of its definitions resolve through.
+pub struct EvalStack {
+    stack: Vec<EvalStackItem>,
+    natives: EvalNatives,
+    interner: Interner,
 }
 
-impl<'a> EvalStack<'a> {
-    /// Create a new definition stack.
-    pub fn new<Iter>(iter: Iter) -> Self
-    where
-        Iter: AdvanceSemNodeIterator<'a> + 'a,
-    {
+impl EvalStack {
+    /// Create a new definition stack with the builtin native functions.
+    pub fn new() -> Self {
+        Self::with_nodes([], Interner::new())
+    }
+
+    /// Create a new definition stack evaluating `nodes`, whose symbols
+    /// come from `interner`.
+    pub(crate) fn with_nodes(nodes: impl Into<Arc<[SemNode]>>, mut interner: Interner) -> Self {
         Self {
-            stack: vec![EvalStackItem {
-                scope: vec![],
-                iter: Box::new(iter),
-            }],
+            stack: vec![EvalStackItem::new(nodes.into())],
+            natives: EvalNatives::with_builtins(&mut interner),
+            interner,
         }
     }
 
+    /// Get the interner the symbols of the stack resolve through.
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Get the interner mutably, to intern more symbols.
+    pub fn interner_mut(&mut self) -> &mut Interner {
+        &mut self.interner
+    }
+
+    /// Get the native functions.
+    pub(crate) fn natives(&self) -> &EvalNatives {
+        &self.natives
+    }
+
+    /// Get the native functions mutably, to register more.
+    pub(crate) fn natives_mut(&mut self) -> &mut EvalNatives {
+        &mut self.natives
+    }
+
+    /// Register the numeric extension, see [`EvalNatives::register_numeric`].
+    pub(crate) fn register_numeric(&mut self) {
+        self.natives.register_numeric(&mut self.interner);
+    }
+
     /// Push a new scope onto the stack.
-    pub fn push_scope<Iter>(&mut self, iter: Iter)
-    where
-        Iter: AdvanceSemNodeIterator<'a> + 'a,
-    {
-        self.stack.push(EvalStackItem {
-            scope: vec![],
-            iter: Box::new(iter),
-        });
+    pub(crate) fn push_scope(&mut self, nodes: impl Into<Arc<[SemNode]>>) {
+        self.stack.push(EvalStackItem::new(nodes.into()));
+    }
+
+    /// Replace the nodes of the root scope.
+    ///
+    /// Definitions already on the stack are kept, so new nodes can be
+    /// evaluated against them.
+    pub(crate) fn load(&mut self, nodes: impl Into<Arc<[SemNode]>>) {
+        let item = self.stack.first_mut().expect("scope is in stack");
+        item.nodes = nodes.into();
+        item.pos = 0;
+    }
+
+    /// Take the next node of the current scope to evaluate.
+    pub(crate) fn next_node(&mut self) -> Option<SemNode> {
+        let item = self.stack.last_mut().expect("scope in stack");
+        let node = item.nodes.get(item.pos)?.clone();
+        item.pos += 1;
+        Some(node)
     }
 
     /// Pop a new scope from the stack.
-    pub fn pop_scope(&mut self) -> Option<EvalStackItem<'a>> {
+    pub(crate) fn pop_scope(&mut self) -> Option<EvalStackItem> {
         self.stack.pop()
     }
 
     /// Push a new definition onto the stack.
-    pub fn push_def(&mut self, key: EvalIdents, value: EvalDefValue<'a>) {
+    pub fn push_def(
+        &mut self,
+        key: EvalIdents,
+        value: EvalDefValue,
+        span: Span,
+    ) -> Result<(), EvalError> {
         if key.iter().all(|x| matches!(x, EvalIdentsKind::Param(_))) {
-            panic!("a definition must have at least one non-parameter identifier")
+            return Err(EvalError::ParamOnlySignature { span });
+        }
+
+        if let Some(param) = key.duplicate_param() {
+            return Err(EvalError::Idents {
+                error: EvalIdentsError::DuplicateParam(param),
+                span,
+            });
         }
 
-        self.stack
-            .last_mut()
-            .expect("scope is in stack")
-            .scope
-            .push((key, value));
+        let item = self.stack.last_mut().expect("scope is in stack");
+        item.index.insert(&key, item.scope.len());
+        item.scope.push(EvalStackDef { key, value, span });
+
+        Ok(())
+    }
+
+    /// Get the definitions of every scope, from the root scope.
+    pub fn scopes(&self) -> impl Iterator<Item = &[EvalStackDef]> {
+        self.stack.iter().map(|item| &item.scope[..])
+    }
+
+    /// Get the last definition pushed onto the current scope.
+    pub fn last_def(&self) -> Option<&EvalStackDef> {
+        self.stack.last()?.scope.last()
     }
 
     /// Resolve an identifier.
+    ///
+    /// The latest definition of the innermost scope matching `ident` wins.
+    /// Native functions are resolved after every scope.
     pub fn resolve<'stack>(
         &'stack self,
         ident: &EvalIdents,
-    ) -> Option<EvalStackResolveResult<'a, 'stack>> {
+    ) -> Result<Option<EvalStackResolveResult<'stack>>, EvalError> {
         if ident.len() == 0 {
-            return None;
+            return Ok(None);
         }
 
-        for items in self.stack.iter().rev() {
-            for (key, value) in items.scope.iter().rev() {
-                if let Some(args) = key.matches(ident) {
-                    return Some(EvalStackResolveResult { key, value, args });
-                }
+        // Parameters are an error on the first definition reached, so every definition is tried
+        let defs: Box<dyn Iterator<Item = &EvalStackDef>> = match has_param(ident) {
+            true => Box::new(
+                self.stack
+                    .iter()
+                    .rev()
+                    .flat_map(|item| item.scope.iter().rev()),
+            ),
+            false => Box::new(self.stack.iter().rev().flat_map(|item| {
+                item.index
+                    .candidates(ident)
+                    .into_iter()
+                    .map(|idx| &item.scope[idx])
+            })),
+        };
+        let defs = defs.chain(self.natives.defs());
+
+        for EvalStackDef { key, value, span } in defs {
+            let args = key.matches(ident).map_err(|error| EvalError::Idents {
+                error,
+                span: span.clone(),
+            })?;
+
+            if let Some(args) = args {
+                return Ok(Some(EvalStackResolveResult {
+                    key,
+                    value,
+                    span,
+                    args,
+                }));
             }
         }
 
-        None
+        Ok(None)
     }
 }
 
-impl<'a> Iterator for EvalStack<'a> {
-    type Item = &'a SemNode;
+/// Check if identifiers contain a parameter, including in inner expressions.
+fn has_param(idents: &EvalIdents) -> bool {
+    idents.iter().any(|ident| match ident {
+        EvalIdentsKind::Param(_) => true,
+        EvalIdentsKind::Inner(inner) => has_param(inner),
+        EvalIdentsKind::Expr(_) | EvalIdentsKind::Str(_) | EvalIdentsKind::Int(_) => false,
+    })
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.stack.last_mut().expect("scope in stack").iter.next()
+impl Default for EvalStack {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<'a> std::fmt::Debug for EvalStack<'a> {
+impl std::fmt::Debug for EvalStack {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         struct KeyValuePair<'a> {
             key: &'a EvalIdents,
-            value: &'a EvalDefValue<'a>,
+            value: &'a EvalDefValue,
+            interner: &'a Interner,
         }
 
         impl std::fmt::Debug for KeyValuePair<'_> {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                f.write_fmt(format_args!("{:?}: {:?}", self.key, self.value))
+                f.write_fmt(format_args!(
+                    "{:?}: {:?}",
+                    self.key.simple_display(self.interner),
+                    self.value.simple_display(self.interner)
+                ))
             }
         }
 
@@ -113,9 +244,114 @@ impl<'a> std::fmt::Debug for EvalStack<'a> {
             .entries(self.stack.iter().map(|x| {
                 x.scope
                     .iter()
-                    .map(|(key, value)| KeyValuePair { key, value })
+                    .map(|EvalStackDef { key, value, .. }| KeyValuePair {
+                        key,
+                        value,
+                        interner: &self.interner,
+                    })
                     .collect::<Vec<_>>()
             }))
             .finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_signature, SimpleDisplay, SpanPos};
+
+    fn span(line: usize) -> Span {
+        Span::new(
+            SpanPos {
+                line,
+                col: 1,
+                idx: 0,
+            },
+            0,
+        )
+    }
+
+    /// Resolve by trying every definition, latest first.
+    fn resolve_linear<'s>(stack: &'s EvalStack, ident: &EvalIdents) -> Option<&'s Span> {
+        let scopes = stack.scopes().collect::<Vec<_>>();
+        scopes
+            .into_iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|def| matches!(def.key.matches(ident), Ok(Some(_))))
+            .map(|def| &def.span)
+    }
+
+    #[test]
+    fn test_stack_resolve() {
+        let mut stack = EvalStack::new();
+        let root = [
+            "1", "2", "+", "-", "$a + $b", "1 + 1", "$a - $b", "f $x", "$a + $b",
+        ];
+        for (line, key) in root.into_iter().enumerate() {
+            let key = parse_signature(key, stack.interner_mut());
+            stack
+                .push_def(key, EvalDefValue::Base, span(line + 1))
+                .unwrap();
+        }
+        let inner = vec![
+            EvalIdentsKind::Inner(parse_signature("$x", stack.interner_mut())),
+            EvalIdentsKind::Param(stack.interner_mut().intern("$y")),
+        ];
+        stack.push_scope([]);
+        for (line, key) in [parse_signature("f 1", stack.interner_mut()), inner]
+            .into_iter()
+            .enumerate()
+        {
+            stack
+                .push_def(key, EvalDefValue::Base, span(line + 101))
+                .unwrap();
+        }
+
+        let idents = [
+            parse_signature("1", stack.interner_mut()),
+            parse_signature("1 + 1", stack.interner_mut()),
+            parse_signature("1 + 2", stack.interner_mut()),
+            parse_signature("2 - 1", stack.interner_mut()),
+            parse_signature("f 1", stack.interner_mut()),
+            parse_signature("f 2", stack.interner_mut()),
+            parse_signature("g 1", stack.interner_mut()),
+            vec![
+                EvalIdentsKind::Inner(parse_signature("1", stack.interner_mut())),
+                EvalIdentsKind::Expr(stack.interner_mut().intern("2")),
+            ],
+        ];
+        let lines = idents
+            .iter()
+            .map(|ident| {
+                let result = stack.resolve(ident).unwrap();
+                assert_eq!(
+                    result.as_ref().map(|result| result.span),
+                    resolve_linear(&stack, ident),
+                    "{}",
+                    ident.simple_display(stack.interner()),
+                );
+                result.map(|result| result.span.start.line)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                Some(1),
+                Some(9),
+                Some(9),
+                Some(7),
+                Some(101),
+                Some(8),
+                None,
+                Some(102)
+            ]
+        );
+
+        let dbg = parse_signature("dbg! 1", stack.interner_mut());
+        let dbg = stack.resolve(&dbg).unwrap().unwrap();
+        assert_eq!(dbg.key.simple_display(stack.interner()), "dbg! $x");
+        let param = parse_signature("1 + $x", stack.interner_mut());
+        assert!(stack.resolve(&param).is_err());
+    }
+}
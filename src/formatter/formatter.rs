@@ -0,0 +1,322 @@
+use crate::{
+    CollectDiagnostics, Cst, CstNode, CstNodeKind, CstParser, CstToken, Diagnostics, Interner,
+    TokenKind,
+};
+
+/// Indentation of one level of definition bodies.
+const FORMATTER_INDENT: &str = "    ";
+
+/// Format a source into its canonical layout.
+///
+/// Sources with syntax errors are not formatted, their errors are returned.
+pub fn format_src(src: &str) -> Result<String, Diagnostics> {
+    // Formatting keeps the text of each token, so identifiers are only interned to parse
+    let cst = CstParser::parse_src(src, &mut Interner::new());
+    let mut diagnostics = Diagnostics::new();
+    cst.syn_nodes().collect_diagnostics(&mut diagnostics);
+    match diagnostics.has_errors() {
+        true => {
+            diagnostics.sort();
+            Err(diagnostics)
+        }
+        false => Ok(Formatter::new().format(&cst)),
+    }
+}
+
+/// Pretty-printer of concrete syntax trees.
+///
+/// Every definition starts a line, and bodies are indented on their own lines
+/// unless they only hold expressions. Comments are kept, along with single
+/// blank lines between definitions.
+#[derive(Debug, Clone, Default)]
+pub struct Formatter {
+    out: String,
+    indent: usize,
+
+    /// Whether the current line has any text.
+    in_line: bool,
+
+    /// Whether the next text must start a new line, after a line comment.
+    break_line: bool,
+
+    /// Whether the last text is an opening bracket.
+    after_open: bool,
+
+    /// Whether nothing has been written in the current block.
+    block_start: bool,
+}
+
+impl Formatter {
+    /// Create a new formatter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Format a tree.
+    pub fn format(mut self, cst: &Cst) -> String {
+        self.block_start = true;
+        self.block(&cst.nodes, &cst.trailing);
+        self.newline();
+        self.out
+    }
+
+    /// Write the definitions of a block, each starting a line, then the
+    /// expressions that end it.
+    fn block(&mut self, nodes: &[CstNode], inner: &[CstToken]) {
+        let mut start = 0;
+        for (i, node) in nodes.iter().enumerate() {
+            if is_body(node) {
+                self.item(&nodes[start..=i]);
+                start = i + 1;
+            }
+        }
+        if start < nodes.len() {
+            self.item(&nodes[start..]);
+        }
+        self.trivia(inner);
+    }
+
+    /// Write a definition or expressions on a new line.
+    fn item(&mut self, nodes: &[CstNode]) {
+        let newlines = self.trivia(&nodes[0].leading);
+        match newlines {
+            0 | 1 => self.newline(),
+            _ => self.blank_line(),
+        }
+        self.content(&nodes[0]);
+        nodes[1..].iter().for_each(|node| self.node(node));
+    }
+
+    /// Write a node with its trivia.
+    fn node(&mut self, node: &CstNode) {
+        self.trivia(&node.leading);
+        self.content(node);
+    }
+
+    /// Write a node and its trailing trivia.
+    fn content(&mut self, node: &CstNode) {
+        match &node.kind {
+            CstNodeKind::Token(token) => self.text(&token.text, true),
+            CstNodeKind::Brac {
+                children, inner, ..
+            } if is_body(node) => self.body(children, inner),
+            CstNodeKind::Brac {
+                children, inner, ..
+            } => {
+                self.text("(", true);
+                self.after_open = true;
+                children.iter().for_each(|child| self.node(child));
+                self.trivia(inner);
+                self.text(")", false);
+            }
+            // Only sources with syntax errors have them, they are written as they are
+            CstNodeKind::TooDeep(tokens) => self.text(
+                &tokens.iter().map(|x| x.to_string()).collect::<String>(),
+                true,
+            ),
+        }
+        self.trivia(&node.trailing);
+    }
+
+    /// Write a definition body, inline if it only holds expressions.
+    fn body(&mut self, children: &[CstNode], inner: &[CstToken]) {
+        let inline = !children.iter().any(is_body) && !has_comment(children, inner);
+        self.text("{", true);
+        match (inline, children) {
+            (true, []) => self.text("}", false),
+            (true, children) => {
+                children.iter().for_each(|child| self.node(child));
+                self.text("}", true);
+            }
+            (false, children) => {
+                self.indent += 1;
+                self.newline();
+                self.block_start = true;
+                self.block(children, inner);
+                self.indent -= 1;
+                self.newline();
+                self.text("}", false);
+            }
+        }
+    }
+
+    /// Write the comments of trivia, on their own line if a newline comes
+    /// before them.
+    ///
+    /// Returns the number of newlines after the last comment.
+    fn trivia(&mut self, tokens: &[CstToken]) -> usize {
+        let mut newlines = 0;
+        for token in tokens {
+            match token.token.value {
+                TokenKind::Newlines => newlines += token.text.replace("\r\n", "\n").len(),
+                TokenKind::Comment(_) => {
+                    match newlines {
+                        0 => {}
+                        1 => self.newline(),
+                        _ => self.blank_line(),
+                    }
+                    self.text(token.text.trim_end(), true);
+                    self.break_line = true;
+                    newlines = 0;
+                }
+                _ => {}
+            }
+        }
+        newlines
+    }
+
+    /// Write text, separated by a space from the previous text of the line
+    /// if `space` is true.
+    fn text(&mut self, text: &str, space: bool) {
+        if self.break_line {
+            self.newline();
+        }
+        if !self.in_line {
+            self.out.push_str(&FORMATTER_INDENT.repeat(self.indent));
+        } else if space && !self.after_open {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.in_line = true;
+        self.after_open = false;
+        self.block_start = false;
+    }
+
+    /// End the current line, if it has any text.
+    fn newline(&mut self) {
+        if self.in_line {
+            self.out.push('\n');
+            self.in_line = false;
+        }
+        self.break_line = false;
+    }
+
+    /// End the current line, then leave a blank line unless the block is empty.
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.block_start {
+            self.out.push('\n');
+        }
+    }
+}
+
+/// Check if a node is a definition body.
+fn is_body(node: &CstNode) -> bool {
+    matches!(
+        &node.kind,
+        CstNodeKind::Brac { open, .. } if open.token.value == TokenKind::OpenBrac('{')
+    )
+}
+
+/// Check if nodes or the trivia after them hold a comment.
+fn has_comment(nodes: &[CstNode], inner: &[CstToken]) -> bool {
+    let is_comment = |x: &CstToken| x.token.value.is_comment();
+    inner.iter().any(is_comment)
+        || nodes.iter().any(|node| {
+            node.leading.iter().chain(&node.trailing).any(is_comment)
+                || match &node.kind {
+                    CstNodeKind::Token(_) => false,
+                    CstNodeKind::TooDeep(tokens) => tokens.iter().any(is_comment),
+                    CstNodeKind::Brac {
+                        children, inner, ..
+                    } => has_comment(children, inner),
+                }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SrcCodeIterExt;
+    use proptest::prelude::*;
+
+    /// Get the tokens of a source without spaces and newlines, with the
+    /// trailing whitespaces of comments trimmed.
+    fn tokens(src: &str, interner: &mut Interner) -> Vec<TokenKind> {
+        src.char_indices()
+            .src_code()
+            .lexer(interner)
+            .filter_map(|token| match token.value {
+                TokenKind::Spaces | TokenKind::Newlines => None,
+                TokenKind::Comment(text) => Some(TokenKind::Comment(text.trim_end().to_string())),
+                kind => Some(kind),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_format_src() {
+        let cases = [
+            ("", ""),
+            ("1 {}   + {}", "1 {}\n+ {}\n"),
+            (
+                "f $x {  g {}\n  g { ( $x   +  1 ) }\n\n\n  g }",
+                "f $x {\n    g {}\n    g { ($x + 1) }\n\n    g\n}\n",
+            ),
+            ("x  { \"a  b\"\n}", "x { \"a  b\" }\n"),
+            (
+                "\n\n// a  \n\n\n\nx {} // b\n{ c }",
+                "// a\n\nx {} // b\n{ c }\n",
+            ),
+            (
+                "x // a\n{ (1 // b\n) }",
+                "x // a\n{\n    (1 // b\n    )\n}\n",
+            ),
+            ("h { a {} // c\n}", "h {\n    a {} // c\n}\n"),
+        ];
+        for (src, formatted) in cases {
+            assert_eq!(format_src(src).unwrap(), formatted, "{src:?}");
+        }
+
+        let demo = include_str!("../../examples/demo.deck");
+        assert_eq!(format_src(demo).unwrap(), demo);
+
+        let errors = format_src("a { ( }\n\"b").unwrap_err();
+        assert_eq!(errors.len(), 3);
+    }
+
+    /// Generate sources without syntax errors.
+    fn src_strategy() -> impl Strategy<Value = String> {
+        let leaf = prop_oneof![
+            Just("a"),
+            Just("$b"),
+            Just(" "),
+            Just("\t"),
+            Just("\n"),
+            Just("\r\n"),
+            Just(" // c ) \n"),
+            Just("\"s  t\""),
+        ]
+        .prop_map(String::from);
+        leaf.prop_recursive(4, 64, 6, |inner| {
+            prop::collection::vec(inner, 0..6).prop_flat_map(|items| {
+                let src = items.concat();
+                prop_oneof![
+                    Just(src.clone()),
+                    Just(format!("({src})")),
+                    Just(format!("{{{src}}}")),
+                ]
+            })
+        })
+    }
+
+    #[test]
+    fn test_format_src_too_deep() {
+        let nested = "(".repeat(100_000) + "a" + &")".repeat(100_000);
+        let err = format_src(&format!("x {{ {nested} }}")).unwrap_err();
+        assert_eq!(
+            err.iter().map(|x| x.msg.as_str()).collect::<Vec<_>>(),
+            ["Brackets nested too deep: more than 256 levels"],
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_format_src_idempotent(src in src_strategy()) {
+            let formatted = format_src(&src).unwrap();
+            prop_assert_eq!(format_src(&formatted).unwrap(), formatted.clone());
+            let mut interner = Interner::new();
+            prop_assert_eq!(tokens(&formatted, &mut interner), tokens(&src, &mut interner));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::{Diagnostic, Diagnostics, EvalError, RenderStyle, Sources, SrcId};
+
+/// Interpreter error, with the source it comes from.
+#[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
+pub enum InterpreterError {
+    /// Errors found before evaluation.
+    #[error("{name}: {} error(s) found before evaluation", .diagnostics.len())]
+    Check {
+        name: String,
+        src: Arc<str>,
+        diagnostics: Diagnostics,
+    },
+
+    /// Error during evaluation.
+    ///
+    /// `sources` are the sources loaded so far, since the spans of the error
+    /// can point into any of them. `diagnostic` names the identifiers of the
+    /// error, which only resolve through the interpreter.
+    #[error("{name}: {}", .diagnostic.msg)]
+    Eval {
+        name: String,
+        src: Arc<str>,
+        error: EvalError,
+        diagnostic: Box<Diagnostic>,
+        sources: Sources,
+    },
+}
+
+impl InterpreterError {
+    /// Get the name of the source.
+    pub fn name(&self) -> &str {
+        match self {
+            InterpreterError::Check { name, .. } | InterpreterError::Eval { name, .. } => name,
+        }
+    }
+
+    /// Get the source code.
+    pub fn src(&self) -> &str {
+        match self {
+            InterpreterError::Check { src, .. } | InterpreterError::Eval { src, .. } => src,
+        }
+    }
+
+    /// Get the diagnostics of the error.
+    pub fn diagnostics(&self) -> Diagnostics {
+        match self {
+            InterpreterError::Check { diagnostics, .. } => diagnostics.clone(),
+            InterpreterError::Eval { diagnostic, .. } => {
+                [Diagnostic::clone(diagnostic)].into_iter().collect()
+            }
+        }
+    }
+
+    /// Render the diagnostics of the error, each label with its own source.
+    ///
+    /// Spans without a source identifier point into the source of the error.
+    pub fn render(&self, style: RenderStyle) -> String {
+        match self {
+            InterpreterError::Check {
+                name,
+                src,
+                diagnostics,
+            } => diagnostics.render(name, src, style),
+            InterpreterError::Eval {
+                name, src, sources, ..
+            } => self.diagnostics().render_with(
+                |id| match id == SrcId::default() {
+                    true => Some((name, src)),
+                    false => sources.get(id),
+                },
+                style,
+            ),
+        }
+    }
+}
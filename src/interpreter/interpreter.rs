@@ -0,0 +1,665 @@
+use std::sync::Arc;
+
+use super::*;
+use crate::{
+    check_exprs_with_id, check_with_id, parse_signature, Diagnostic, EvalDebugOption, EvalDefValue,
+    EvalError, EvalIdents, EvalIdentsIdentOption, EvalLimits, EvalNativeArgs, EvalNativeFn,
+    EvalObserver, EvalOutput, EvalStack, EvalStackResolveResult, Evaluator, Interner, SemNode,
+    SemNodeExpr, SemNodeKind, Sources, Span, SrcCodeIterExt, SrcId,
+};
+
+/// Interpreter.
+///
+/// Definitions persist between calls, so later sources can use the
+/// definitions of earlier ones.
+///
+/// Loaded sources are kept to render errors whose spans point into them.
+#[derive(Debug)]
+pub struct Interpreter {
+    evaluator: Evaluator,
+    sources: Sources,
+}
+
+// Evaluation errors carry their diagnostic, since their identifiers only resolve
+// through the interner of the interpreter
+#[allow(clippy::result_large_err)]
+impl Interpreter {
+    /// Create a new interpreter without definitions.
+    pub fn new() -> Self {
+        Self::new_with_debug(EvalDebugOption::NONE)
+    }
+
+    /// Create a new interpreter with debug options.
+    pub fn new_with_debug(debug_options: EvalDebugOption) -> Self {
+        Self {
+            evaluator: Evaluator::new_with_debug([], debug_options, Interner::new()),
+            sources: Sources::new(),
+        }
+    }
+
+    /// Get the definition stack.
+    pub fn stack(&self) -> &EvalStack {
+        self.evaluator.stack()
+    }
+
+    /// Get the interner the symbols of the interpreter resolve through.
+    pub fn interner(&self) -> &Interner {
+        self.evaluator.interner()
+    }
+
+    /// Get the debug options.
+    pub fn debug_options(&self) -> EvalDebugOption {
+        self.evaluator.debug_options()
+    }
+
+    /// Set the debug options.
+    pub fn set_debug_options(&mut self, debug_options: EvalDebugOption) {
+        self.evaluator.set_debug_options(debug_options);
+    }
+
+    /// Get the evaluation limits.
+    pub fn limits(&self) -> EvalLimits {
+        self.evaluator.limits()
+    }
+
+    /// Set the evaluation limits.
+    ///
+    /// The step limit applies to each loaded source and evaluated string.
+    pub fn set_limits(&mut self, limits: EvalLimits) {
+        self.evaluator.set_limits(limits);
+    }
+
+    /// Set the output sink of `dbg!` and debug traces, stdout by default.
+    pub fn set_output(&mut self, output: impl EvalOutput + Send + 'static) {
+        self.evaluator.set_output(output);
+    }
+
+    /// Add an observer of evaluation events.
+    pub fn add_observer(&mut self, observer: impl EvalObserver + Send + 'static) {
+        self.evaluator.add_observer(observer);
+    }
+
+    /// Check if source code ends inside an unclosed bracket,
+    /// and more of it is needed before it can be loaded.
+    pub fn is_incomplete(text: &str) -> bool {
+        // Only brackets matter, so identifiers are interned into a throwaway interner
+        text.char_indices()
+            .src_code()
+            .lexer(&mut Interner::new())
+            .parse_syn()
+            .is_incomplete()
+    }
+
+    /// Check and evaluate a source.
+    ///
+    /// Nothing is evaluated if the source has errors, and evaluation stops at
+    /// the first evaluation error.
+    pub fn load_source(
+        &mut self,
+        name: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Result<(), InterpreterError> {
+        let (name, src): (String, Arc<str>) = (name.into(), text.into().into());
+
+        let id = self.sources.push(name.as_str(), src.clone());
+        let result = check_with_id(&src, id, self.evaluator.interner_mut());
+        if result.diagnostics.has_errors() {
+            self.sources.pop();
+            return Err(InterpreterError::Check {
+                name,
+                src,
+                diagnostics: result.diagnostics,
+            });
+        }
+
+        self.load_nodes(result.nodes)
+            .map_err(|error| self.eval_error(name, src, error))
+    }
+
+    /// Define `signature` with `body`, the source between the curly brackets.
+    ///
+    /// `define("mul_2 $x", "$x + $x")` is the same as loading `mul_2 $x { $x + $x }`.
+    pub fn define(&mut self, signature: &str, body: &str) -> Result<(), InterpreterError> {
+        let name = String::from("<define>");
+        // The closing bracket is on its own line, so a comment ending the body cannot hide it
+        let src: Arc<str> = format!("{signature} {{ {body}\n}}").into();
+
+        let id = self.sources.push(name.as_str(), src.clone());
+        let mut result = check_with_id(&src, id, self.evaluator.interner_mut());
+        let single_def = matches!(
+            &result.nodes[..],
+            [SemNode { value: SemNodeKind::Def { idents, .. }, .. }] if !idents.is_empty(),
+        );
+        if !single_def && !result.diagnostics.has_errors() {
+            result.diagnostics.push(
+                Diagnostic::error("expected a single definition", Span::of_src(&src))
+                    .with_label("defines more or less than one signature"),
+            );
+        }
+        if result.diagnostics.has_errors() {
+            self.sources.pop();
+            return Err(InterpreterError::Check {
+                name,
+                src,
+                diagnostics: result.diagnostics,
+            });
+        }
+
+        self.load_nodes(result.nodes)
+            .map_err(|error| self.eval_error(name, src, error))
+    }
+
+    /// Register a native function.
+    ///
+    /// Words of `signature` starting with `$` are parameters:
+    /// `read-file $path` binds the argument of `read-file` to `$path`.
+    ///
+    /// The arguments are passed as written, use
+    /// [`Interpreter::register_eager_native`] if the function evaluates them.
+    pub fn register_native<F>(&mut self, signature: &str, f: F) -> Result<(), InterpreterError>
+    where
+        F: Fn(&mut Evaluator, &EvalNativeArgs) -> Result<EvalIdents, EvalError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.register_native_fn(signature, EvalNativeFn::new(f))
+    }
+
+    /// Register a native function that evaluates its arguments.
+    ///
+    /// The analyzer then checks that the arguments resolve,
+    /// like the arguments of a base definition.
+    pub fn register_eager_native<F>(
+        &mut self,
+        signature: &str,
+        f: F,
+    ) -> Result<(), InterpreterError>
+    where
+        F: Fn(&mut Evaluator, &EvalNativeArgs) -> Result<EvalIdents, EvalError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.register_native_fn(signature, EvalNativeFn::new(f).with_eager_args())
+    }
+
+    /// Register a native function for a signature.
+    fn register_native_fn(
+        &mut self,
+        signature: &str,
+        f: EvalNativeFn,
+    ) -> Result<(), InterpreterError> {
+        let key = parse_signature(signature, self.evaluator.interner_mut());
+        self.evaluator
+            .register_native(key, f)
+            .map_err(|error| self.eval_error(String::from("<native>"), signature.into(), error))
+    }
+
+    /// Check if the numeric extension is registered.
+    pub fn numeric(&self) -> bool {
+        self.evaluator.stack().natives().numeric()
+    }
+
+    /// Register the numeric extension.
+    ///
+    /// Integer literals are values, and `+ - * / % < =` compute on them
+    /// unless a definition of the same signature shadows them.
+    pub fn register_numeric(&mut self) {
+        self.evaluator.register_numeric();
+    }
+
+    /// Evaluate expressions against the current definitions.
+    ///
+    /// Returns the identifiers the expressions evaluate to.
+    pub fn eval_str(&mut self, text: &str) -> Result<EvalIdents, InterpreterError> {
+        let (name, src): (String, Arc<str>) = (String::from("<eval>"), text.into());
+
+        // The expressions are not kept, so neither is their source
+        let id = self.sources.push(name.as_str(), src.clone());
+        let result = match self.check_exprs(&name, &src, id) {
+            Ok(exprs) => match self.eval_exprs(&exprs, &src) {
+                Ok(idents) => Ok(idents),
+                Err(error) => Err(self.eval_error(name, src, error)),
+            },
+            Err(err) => Err(err),
+        };
+        self.sources.pop();
+        result
+    }
+
+    /// Find the definition expressions resolve to, without evaluating it.
+    pub fn query(
+        &mut self,
+        text: &str,
+    ) -> Result<Option<EvalStackResolveResult<'_>>, InterpreterError> {
+        let (name, src): (String, Arc<str>) = (String::from("<query>"), text.into());
+
+        let id = self.sources.push(name.as_str(), src.clone());
+        let idents = match self.check_exprs(&name, &src, id) {
+            Ok(exprs) => match self
+                .evaluator
+                .eval_idents(&exprs, EvalIdentsIdentOption::AlwaysExpr)
+            {
+                Ok(idents) => Ok(idents),
+                Err(error) => Err(self.eval_error(name.clone(), src.clone(), error)),
+            },
+            Err(err) => Err(err),
+        };
+        self.sources.pop();
+        let idents = idents?;
+
+        match self.evaluator.stack().resolve(&idents) {
+            Ok(result) => Ok(result),
+            Err(error) => Err(self.eval_error(name, src, error)),
+        }
+    }
+
+    /// Evaluate checked nodes.
+    fn load_nodes(&mut self, nodes: Vec<SemNode>) -> Result<(), EvalError> {
+        self.evaluator.reset_steps();
+        self.evaluator.load(nodes);
+        self.evaluator.by_ref().collect()
+    }
+
+    /// Create an evaluation error with the sources loaded so far.
+    fn eval_error(&self, name: String, src: Arc<str>, error: EvalError) -> InterpreterError {
+        InterpreterError::Eval {
+            name,
+            src,
+            diagnostic: Box::new(error.diagnostic(self.evaluator.interner())),
+            error,
+            sources: self.sources.clone(),
+        }
+    }
+
+    /// Check source code made of expressions only.
+    fn check_exprs(
+        &mut self,
+        name: &str,
+        src: &Arc<str>,
+        id: SrcId,
+    ) -> Result<Vec<SemNodeExpr>, InterpreterError> {
+        let result = check_exprs_with_id(src, id, self.evaluator.interner_mut());
+        match result.diagnostics.has_errors() {
+            true => Err(InterpreterError::Check {
+                name: name.to_string(),
+                src: src.clone(),
+                diagnostics: result.diagnostics,
+            }),
+            false => Ok(result.exprs),
+        }
+    }
+
+    /// Evaluate checked expressions to identifiers.
+    fn eval_exprs(&mut self, exprs: &[SemNodeExpr], src: &str) -> Result<EvalIdents, EvalError> {
+        let span = match (exprs.first(), exprs.last()) {
+            (Some(first), Some(last)) => first.span.to(&last.span),
+            _ => Span::of_src(src),
+        };
+        self.evaluator.reset_steps();
+        let idents = self
+            .evaluator
+            .eval_idents(exprs, EvalIdentsIdentOption::AlwaysExpr)?;
+        match self.evaluator.eval_exprs(&idents, &span, false)? {
+            Some(EvalDefValue::Ref(idents) | EvalDefValue::Expanded(idents)) => Ok(idents),
+            Some(_) => Ok(vec![]),
+            None => Err(EvalError::IdentsNotFound { idents, span }),
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EvalCapture, EvalIdentsKind, EvalLimit, SimpleDisplay, SynParser};
+
+    fn interpreter() -> Interpreter {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .load_source("prelude", "1 {}\n+ {}\n$a + $b {}\n2 {}\n2 { 1 + 1 }")
+            .unwrap();
+        interpreter
+    }
+
+    #[test]
+    fn test_interpreter_eval_str() {
+        let mut interpreter = interpreter();
+        interpreter.define("3", "").unwrap();
+        interpreter.define("3", "2 + 1").unwrap();
+
+        let value = interpreter.eval_str("3 + 3").unwrap();
+        assert_eq!(
+            value.simple_display(interpreter.interner()),
+            "(((1) + (1)) + (1)) + (((1) + (1)) + (1))"
+        );
+
+        interpreter.define("4", "").unwrap();
+        interpreter.define("4", "3 + 1 // note").unwrap();
+        let value = interpreter.eval_str("4").unwrap();
+        assert_eq!(
+            value.simple_display(interpreter.interner()),
+            "(((1) + (1)) + (1)) + (1)"
+        );
+
+        let err = interpreter.eval_str("5").unwrap_err();
+        assert_eq!(err.name(), "<eval>");
+        assert!(matches!(
+            err,
+            InterpreterError::Eval {
+                error: EvalError::IdentsNotFound { .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_interpreter_native() {
+        let mut interpreter = interpreter();
+        interpreter
+            .register_native("swap $a $b", |evaluator, args| {
+                let interner = evaluator.interner();
+                let (a, b) = (
+                    args.get("$a", interner).unwrap(),
+                    args.get("$b", interner).unwrap(),
+                );
+                match evaluator.eval_exprs(&b, &args.span, false)? {
+                    Some(_) => Ok(vec![
+                        EvalIdentsKind::Inner(b),
+                        EvalIdentsKind::Expr(evaluator.interner_mut().intern("+")),
+                        EvalIdentsKind::Inner(a),
+                    ]),
+                    None => Err(args.error("cannot swap")),
+                }
+            })
+            .unwrap();
+
+        let value = interpreter.eval_str("swap 1 (2 + 1)").unwrap();
+        assert_eq!(
+            value.simple_display(interpreter.interner()),
+            "(2 + 1) + (1)"
+        );
+
+        let err = interpreter.eval_str("swap 1 3").unwrap_err();
+        assert!(matches!(
+            err,
+            InterpreterError::Eval { error: EvalError::Native { msg, .. }, .. } if msg == "cannot swap"
+        ));
+
+        interpreter
+            .load_source("shadow", "swap {}\nswap 1 1 {}")
+            .unwrap();
+        let value = interpreter.eval_str("swap 1 1").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "swap 1 1");
+
+        assert!(interpreter
+            .register_native("$a $b", |_, _| Ok(vec![]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_interpreter_output() {
+        let mut interpreter = interpreter();
+        let capture = EvalCapture::new();
+        interpreter.set_output(capture.clone());
+        interpreter.set_debug_options(EvalDebugOption::CALL);
+
+        interpreter.load_source("dbg", "dbg! { 2 }").unwrap();
+        assert_eq!(capture.dbg(), ["-----------dbg-----------\n2", "(1) + (1)"]);
+        assert_eq!(capture.trace().len(), 1);
+        assert!(capture.trace()[0].starts_with("----------call-----------\n"));
+    }
+
+    #[test]
+    fn test_interpreter_query() {
+        let mut interpreter = interpreter();
+
+        // The result borrows the interpreter, so its symbols resolve through a copy
+        let interner = interpreter.interner().clone();
+        let result = interpreter.query("1 + 2").unwrap().unwrap();
+        assert_eq!(result.key.simple_display(&interner), "$a + $b");
+        assert_eq!(
+            result.args[&interner.get("$b").unwrap()],
+            EvalIdentsKind::Int(2)
+        );
+        assert!(interpreter.query("4").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_interpreter_errors() {
+        let mut interpreter = interpreter();
+
+        let err = interpreter.load_source("bad", "x { ( }").unwrap_err();
+        assert!(matches!(&err, InterpreterError::Check { name, .. } if name == "bad"));
+        assert!(err
+            .render(crate::RenderStyle::Plain)
+            .contains("--> bad:1:7"));
+
+        let err = interpreter.define("a", "b {} } c {").unwrap_err();
+        assert!(matches!(err, InterpreterError::Check { .. }));
+        let err = interpreter.define("a {} b", "1").unwrap_err();
+        assert_eq!(
+            err.diagnostics().iter().next().unwrap().msg,
+            "expected a single definition"
+        );
+    }
+
+    #[test]
+    fn test_interpreter_deep_nesting() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        let mut interpreter = interpreter();
+
+        let err = interpreter
+            .load_source("deep", format!("x {{ {} }}", nested(100_000)))
+            .unwrap_err();
+        assert_eq!(
+            err.diagnostics().iter().next().unwrap().msg,
+            "Brackets nested too deep: more than 256 levels"
+        );
+
+        // The deepest brackets that parse are also evaluated without overflowing
+        let src = format!("x {{ {} }}", nested(SynParser::MAX_DEPTH - 1));
+        let err = interpreter.load_source("deep", src).unwrap_err();
+        assert!(matches!(err, InterpreterError::Eval { .. }));
+    }
+
+    #[test]
+    fn test_interpreter_limits() {
+        fn limit_of(result: Result<impl std::fmt::Debug, InterpreterError>) -> (EvalLimit, usize) {
+            match result {
+                Err(InterpreterError::Eval {
+                    error: EvalError::LimitExceeded { limit, span, .. },
+                    ..
+                }) => (limit, span.start.line),
+                result => panic!("expected a limit error: {result:?}"),
+            }
+        }
+
+        let mut interpreter = interpreter();
+        let src = "f {}\nf $x {\n    y {}\n    f $x\n}";
+        interpreter.load_source("limits", src).unwrap();
+
+        assert_eq!(limit_of(interpreter.eval_str("f 1")), (EvalLimit::Depth, 2));
+
+        interpreter.set_limits(EvalLimits {
+            steps: Some(3),
+            ..EvalLimits::UNLIMITED
+        });
+        interpreter.eval_str("1 + 1").unwrap();
+        interpreter.eval_str("1 + 1").unwrap();
+        assert_eq!(
+            limit_of(interpreter.eval_str("2 + 2")),
+            (EvalLimit::Steps, 1)
+        );
+
+        interpreter.set_limits(EvalLimits {
+            expr_size: Some(10),
+            ..EvalLimits::default()
+        });
+        interpreter.eval_str("2 + 1").unwrap();
+        assert_eq!(
+            limit_of(interpreter.eval_str("2 + 2")),
+            (EvalLimit::ExprSize, 3)
+        );
+    }
+
+    #[test]
+    fn test_interpreter_cycles() {
+        let mut interpreter = interpreter();
+        let src = "a {}\nb {}\na { b }\nb { a }";
+        interpreter.load_source("cycles", src).unwrap();
+
+        let err = interpreter.eval_str("a + 1").unwrap_err();
+        let InterpreterError::Eval { error, .. } = &err else {
+            panic!("expected an evaluation error: {err:?}");
+        };
+        let EvalError::CyclicDefinition { leading, cycle, .. } = error else {
+            panic!("expected a cyclic definition: {error:?}");
+        };
+        let display = |x: &[(EvalIdents, Span)]| {
+            x.iter()
+                .map(|(idents, span)| {
+                    (
+                        idents.simple_display(interpreter.interner()),
+                        span.start.line,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(display(leading), [("a".to_string(), 3)]);
+        assert_eq!(display(cycle), [("b".to_string(), 4)]);
+        assert_eq!(
+            error.message(interpreter.interner()),
+            "cyclic definition: a -> b -> b"
+        );
+        assert_eq!(err.to_string(), "<eval>: cyclic definition: a -> b -> b");
+
+        // Progress is made when a reference expands to new identifiers
+        interpreter.define("c", "").unwrap();
+        interpreter.define("c", "2 + 2").unwrap();
+        interpreter.eval_str("c + c").unwrap();
+    }
+
+    #[test]
+    fn test_interpreter_numeric() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_numeric();
+        interpreter.register_numeric();
+
+        let value = interpreter.eval_str("(2 * 3) + 1").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "7");
+        let value = interpreter.eval_str("(7 % 4) < 5").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "1");
+
+        interpreter
+            .load_source("prelude", "x {}\n3 {}\n3 { 1 + 2 }")
+            .unwrap();
+        let value = interpreter.eval_str("x + (1 + 1)").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "(x) + (2)");
+        let value = interpreter.eval_str("3 * 3").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "9");
+
+        // Computed integers are not interned, so counting does not grow the interner
+        interpreter.define("n", "").unwrap();
+        interpreter.define("n", "0").unwrap();
+        let len = interpreter.interner().len();
+        for _ in 0..100 {
+            interpreter.define("n", "n + 1").unwrap();
+        }
+        let value = interpreter.eval_str("n").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "100");
+        assert_eq!(interpreter.interner().len(), len);
+
+        let err = interpreter.eval_str("1 / 0").unwrap_err();
+        assert!(matches!(
+            err,
+            InterpreterError::Eval { error: EvalError::Native { msg, .. }, .. } if msg == "division by zero"
+        ));
+        let err = interpreter
+            .eval_str(&format!("{} + 1", i64::MAX))
+            .unwrap_err();
+        assert!(matches!(err, InterpreterError::Eval { .. }));
+
+        interpreter
+            .load_source("shadow", "+ {}\n$a + $b {}")
+            .unwrap();
+        let value = interpreter.eval_str("1 + 1").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "(1) + (1)");
+    }
+
+    #[test]
+    fn test_interpreter_str() {
+        let mut interpreter = interpreter();
+        let src = "greet {}\ngreet $name {\n    hello {}\n    hello { $name }\n    hello\n}\nsay {}\nsay \"hi\" {}";
+        interpreter.load_source("str", src).unwrap();
+
+        let value = interpreter.eval_str("greet \"a \\\"b\\\"\"").unwrap();
+        assert_eq!(value, vec![EvalIdentsKind::Str("a \"b\"".into())]);
+        assert_eq!(
+            value.simple_display(interpreter.interner()),
+            "\"a \\\"b\\\"\""
+        );
+
+        let value = interpreter.eval_str("say \"hi\"").unwrap();
+        assert_eq!(value.simple_display(interpreter.interner()), "say \"hi\"");
+        assert!(interpreter.eval_str("say hi").is_err());
+        assert!(interpreter.eval_str("say \"hi!\"").is_err());
+    }
+
+    #[test]
+    fn test_interpreter_render_sources() {
+        let mut interpreter = Interpreter::new();
+        interpreter.load_source("a", "a {}\nb {}\na { b }").unwrap();
+        interpreter.load_source("b", "b { a }").unwrap();
+
+        // The cycle is in earlier sources, one of them shorter than the span
+        let err = interpreter
+            .load_source("c", "éé {\n} dbg! { a }")
+            .unwrap_err();
+        assert_eq!(
+            err.render(crate::RenderStyle::Plain),
+            "\
+error: cyclic definition: a -> b -> b
+ --> c:2:8
+  |
+2 | } dbg! { a }
+  |        ^^^^^ these expressions never finish evaluating
+ ::: a:3:3
+  |
+3 | a { b }
+  |   ----- 'a' refers to 'b'
+ ::: b:1:3
+  |
+1 | b { a }
+  |   ----- 'b' refers to 'b'
+",
+        );
+    }
+
+    #[test]
+    fn test_interpreter_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Interpreter>();
+        assert_send::<Evaluator>();
+
+        // Natives, output and observers move with the interpreter
+        let mut interpreter = Interpreter::new();
+        interpreter.register_numeric();
+        let capture = EvalCapture::new();
+        interpreter.set_output(capture.clone());
+        let interpreter = std::thread::spawn(move || {
+            interpreter.load_source("test", "dbg! { 2 * 3 }").unwrap();
+            interpreter
+        })
+        .join()
+        .unwrap();
+        assert!(interpreter.numeric());
+        assert_eq!(capture.dbg().last().map(String::as_str), Some("6"));
+    }
+}
@@ -0,0 +1,5 @@
+mod error;
+pub use error::*;
+#[allow(clippy::module_inception)]
+mod interpreter;
+pub use interpreter::*;
@@ -0,0 +1,50 @@
+//! Deck interpreter.
+//!
+//! [`Interpreter`] loads and evaluates sources. The items next to it are
+//! what its methods take and return, and the extension points hosts
+//! implement: natives, output sinks, observers and debugger frontends.
+//!
+//! The stages the interpreter is built from are in [`unstable`].
+
+mod analyzer;
+mod debugger;
+mod diagnostics;
+mod evaluator;
+mod formatter;
+mod interpreter;
+mod parsers;
+mod utils;
+
+// Paths such as `crate::Span` are used across the stages
+use diagnostics::*;
+use evaluator::*;
+use parsers::*;
+
+pub use debugger::{
+    Breakpoint, BreakpointError, DebugCommand, DebugFrontend, DebugPause, DebugPauseReason,
+    Debugger,
+};
+pub use diagnostics::{Diagnostic, Diagnostics, Label, RenderStyle, Severity, Sources};
+pub use evaluator::{
+    EvalBody, EvalCapture, EvalDebugObserver, EvalDebugOption, EvalDefValue, EvalError, EvalEvent,
+    EvalFlow, EvalIdents, EvalIdentsError, EvalIdentsKind, EvalLimit, EvalLimits, EvalNativeArgs,
+    EvalNativeFn, EvalObserver, EvalOutput, EvalStack, EvalStackDef, EvalStackResolveResult,
+    EvalStdout, EvalStepNode, Evaluator,
+};
+pub use interpreter::*;
+pub use parsers::{Span, SpanPos, SrcId};
+pub use utils::{Interner, SimpleDisplay, Symbol};
+
+/// Stages the [`Interpreter`] is built from: parsers, evaluator, checks and
+/// the formatter.
+///
+/// Nothing in this module is stable, any item can change or go away in a
+/// minor release.
+pub mod unstable {
+    pub use crate::analyzer::*;
+    pub use crate::diagnostics::*;
+    pub use crate::evaluator::*;
+    pub use crate::formatter::*;
+    pub use crate::parsers::*;
+    pub use crate::utils::*;
+}
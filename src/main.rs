@@ -1,50 +1,16 @@
-mod parsers;
-use parsers::*;
-mod evaluator;
-use evaluator::*;
-mod utils;
-use utils::*;
-
-fn main() {
-    let input = r"
-    { This is a comment }
-
-    { Define bases }
-    1 {}
-    + {}
-    _ {}
-
-    { Define bases with parameters }
-    $1 + $2 {}
-    $ + 1 {}
-
-    { Define constant function '2' }
-    2 {}
-    2 { 1 + 1 }
-    dbg! { 2 }
-
-    { Define constant function '3' }
-    3 {}
-    3 { 2 + 1 }
-    dbg! { 3 }
-
-    { Define function }
-    mul_2_add_3 {}
-    mul_2_add_3 $var {
-        mul_2 {}
-        mul_2 { $var + $var }
-
-        3 + mul_2
+mod cli;
+
+use clap::Parser;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args = cli::Args::parse();
+    let color = args.color;
+    match args.command() {
+        cli::Command::Run(args) => cli::run(&args, color),
+        cli::Command::Check(args) => cli::check(&args, color),
+        cli::Command::Repl(args) => cli::repl(&args, color),
+        cli::Command::Debug(args) => cli::debug(&args, color),
+        cli::Command::Fmt(args) => cli::fmt(&args, color),
     }
-
-    { Print the result with the built-in function 'dbg!' }
-    dbg! { mul_2_add_3 1 }
-    ";
-    input
-        .char_indices()
-        .src_code()
-        .lexer()
-        .parse_syn()
-        .parse_sem()
-        .evaluate_with_debug(EvalDebugOption::NONE);
 }
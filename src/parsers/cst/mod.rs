@@ -0,0 +1,4 @@
+mod parser;
+pub use parser::*;
+mod node;
+pub use node::*;
@@ -0,0 +1,208 @@
+use std::fmt;
+
+use crate::{Span, Spanned, SynErrorKind, SynNode, SynNodeKind, Token, TokenKind};
+
+/// Concrete syntax token: a token with its source text.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CstToken {
+    pub token: Token,
+    pub text: String,
+}
+
+impl CstToken {
+    /// Create a token, taking its text from the source it was lexed from.
+    pub fn new(src: &str, token: Token) -> Self {
+        let text = src[token.span.start.idx..token.span.end_idx()].to_string();
+        Self { token, text }
+    }
+}
+
+impl fmt::Display for CstToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Concrete syntax node kind.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum CstNodeKind {
+    /// Token: an identifier, a string, a token error, or a closing bracket
+    /// without an opening one
+    Token(CstToken),
+
+    /// Brackets: nodes enclosed in brackets, which may be mismatched or unclosed
+    Brac {
+        open: CstToken,
+        children: Vec<CstNode>,
+
+        /// Trivia after the last child, before the closing bracket.
+        inner: Vec<CstToken>,
+        close: Option<CstToken>,
+    },
+
+    /// Too deep: brackets nested more than [`crate::SynParser::MAX_DEPTH`]
+    /// levels, kept as their tokens
+    TooDeep(Vec<CstToken>),
+}
+
+/// Concrete syntax node.
+///
+/// Every token of the source belongs to exactly one node, or to the
+/// trailing trivia of the [`Cst`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CstNode {
+    /// Trivia before the node, since the trailing trivia of the previous one.
+    pub leading: Vec<CstToken>,
+    pub kind: CstNodeKind,
+
+    /// Trivia after the node on its line, up to its newline.
+    pub trailing: Vec<CstToken>,
+}
+
+impl CstNode {
+    /// Get the span of the node, without its trivia.
+    pub fn span(&self) -> Span {
+        match &self.kind {
+            CstNodeKind::Token(token) => token.token.span.clone(),
+            CstNodeKind::Brac {
+                open,
+                children,
+                close,
+                ..
+            } => match (close, children.last()) {
+                (Some(close), _) => open.token.span.to(&close.token.span),
+                (None, Some(last)) => open.token.span.to(&last.span()),
+                (None, None) => open.token.span.clone(),
+            },
+            CstNodeKind::TooDeep(tokens) => {
+                let last = tokens.iter().rev().find(|x| !x.token.value.is_trivia());
+                let first = &tokens[0].token.span;
+                first.to(&last.expect("brackets start with a token").token.span)
+            }
+        }
+    }
+
+    /// Convert the node to the syntactic node [`crate::SynParser`] parses
+    /// from the same tokens.
+    pub fn to_syn(&self) -> SynNode {
+        let value = match &self.kind {
+            CstNodeKind::Token(token) => match &token.token.value {
+                TokenKind::Ident(ident) => SynNodeKind::Ident(*ident),
+                TokenKind::Str(value) => SynNodeKind::Str(value.clone()),
+                TokenKind::Error(kind) => SynNodeKind::Error {
+                    kind: SynErrorKind::Token(kind.clone()),
+                    children: vec![],
+                },
+                TokenKind::CloseBrac(close) => SynNodeKind::Error {
+                    kind: SynErrorKind::UnexpectedCloseBrac {
+                        close: Spanned::new(*close, token.token.span.clone()),
+                    },
+                    children: vec![],
+                },
+                kind => unreachable!("{kind:?} is not a node"),
+            },
+            CstNodeKind::Brac {
+                open,
+                children,
+                close,
+                ..
+            } => {
+                let TokenKind::OpenBrac(open_brac) = open.token.value else {
+                    unreachable!("brackets start with an opening bracket")
+                };
+                let children = children.iter().map(CstNode::to_syn).collect();
+                let open = Spanned::new(open_brac, open.token.span.clone());
+                match close.as_ref().map(|close| &close.token) {
+                    Some(Token {
+                        value: TokenKind::CloseBrac(close),
+                        ..
+                    }) if matches!((open.value, close), ('(', ')') | ('{', '}')) => {
+                        SynNodeKind::Brac {
+                            open: open.value,
+                            close: *close,
+                            children,
+                        }
+                    }
+                    Some(Token {
+                        value: TokenKind::CloseBrac(close),
+                        span,
+                    }) => SynNodeKind::Error {
+                        kind: SynErrorKind::MismatchedBrac {
+                            open,
+                            close: Spanned::new(*close, span.clone()),
+                        },
+                        children,
+                    },
+                    _ => SynNodeKind::Error {
+                        kind: SynErrorKind::UnclosedBrac { open },
+                        children,
+                    },
+                }
+            }
+            CstNodeKind::TooDeep(tokens) => {
+                let TokenKind::OpenBrac(open) = tokens[0].token.value else {
+                    unreachable!("brackets start with an opening bracket")
+                };
+                SynNodeKind::Error {
+                    kind: SynErrorKind::TooDeep {
+                        open: Spanned::new(open, tokens[0].token.span.clone()),
+                    },
+                    children: vec![],
+                }
+            }
+        };
+
+        SynNode {
+            value,
+            span: self.span(),
+        }
+    }
+}
+
+impl fmt::Display for CstNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.leading.iter().try_for_each(|x| x.fmt(f))?;
+        match &self.kind {
+            CstNodeKind::Token(token) => token.fmt(f)?,
+            CstNodeKind::Brac {
+                open,
+                children,
+                inner,
+                close,
+            } => {
+                open.fmt(f)?;
+                children.iter().try_for_each(|x| x.fmt(f))?;
+                inner.iter().try_for_each(|x| x.fmt(f))?;
+                close.iter().try_for_each(|x| x.fmt(f))?;
+            }
+            CstNodeKind::TooDeep(tokens) => tokens.iter().try_for_each(|x| x.fmt(f))?,
+        }
+        self.trailing.iter().try_for_each(|x| x.fmt(f))
+    }
+}
+
+/// Concrete syntax tree.
+///
+/// The tree is lossless: it prints back to the exact source it was parsed from.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Cst {
+    pub nodes: Vec<CstNode>,
+
+    /// Trivia after the last node.
+    pub trailing: Vec<CstToken>,
+}
+
+impl Cst {
+    /// Convert the tree to the syntactic nodes [`crate::SynParser`] parses
+    /// from the same source.
+    pub fn syn_nodes(&self) -> Vec<SynNode> {
+        self.nodes.iter().map(CstNode::to_syn).collect()
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.nodes.iter().try_for_each(|x| x.fmt(f))?;
+        self.trailing.iter().try_for_each(|x| x.fmt(f))
+    }
+}
@@ -0,0 +1,186 @@
+use super::*;
+use crate::{too_deep_len, Interner, SrcCodeIterExt, SynParser, TokenKind};
+
+/// Concrete syntax parser.
+///
+/// Brackets are matched the way [`crate::SynParser`] matches them, and trivia
+/// is attached to the nodes around it.
+#[derive(Debug, Clone)]
+pub struct CstParser {
+    tokens: Vec<CstToken>,
+    pos: usize,
+}
+
+impl CstParser {
+    /// Create a new concrete syntax parser from every token of a source,
+    /// trivia included.
+    pub fn new(tokens: Vec<CstToken>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    /// Lex and parse a source, interning identifiers into `interner`.
+    pub fn parse_src(src: &str, interner: &mut Interner) -> Cst {
+        let tokens = src
+            .char_indices()
+            .src_code()
+            .lexer(interner)
+            .map(|token| CstToken::new(src, token))
+            .collect();
+        Self::new(tokens).parse()
+    }
+
+    /// Parse the tree.
+    pub fn parse(mut self) -> Cst {
+        let (nodes, trailing) = self.parse_nodes(0);
+        Cst { nodes, trailing }
+    }
+
+    /// Parse nodes inside `depth` brackets, until the end of the tokens, or
+    /// until a closing bracket below the top level.
+    ///
+    /// Returns the nodes and the trivia after them.
+    fn parse_nodes(&mut self, depth: usize) -> (Vec<CstNode>, Vec<CstToken>) {
+        let mut nodes = vec![];
+        loop {
+            let leading = self.trivia(true);
+            let kind = match self.tokens.get(self.pos).map(|x| &x.token.value) {
+                None => return (nodes, leading),
+                Some(TokenKind::CloseBrac(_)) if depth > 0 => return (nodes, leading),
+                Some(TokenKind::OpenBrac(_)) if depth >= SynParser::MAX_DEPTH => {
+                    let kinds = self.tokens[self.pos..].iter().map(|x| &x.token.value);
+                    let len = too_deep_len(kinds);
+                    self.pos += len;
+                    CstNodeKind::TooDeep(self.tokens[self.pos - len..self.pos].to_vec())
+                }
+                Some(TokenKind::OpenBrac(_)) => {
+                    let open = self.bump();
+                    let (children, inner) = self.parse_nodes(depth + 1);
+                    let close = match self.tokens.get(self.pos) {
+                        Some(x) if x.token.value.is_close_brac() => Some(self.bump()),
+                        _ => None,
+                    };
+                    CstNodeKind::Brac {
+                        open,
+                        children,
+                        inner,
+                        close,
+                    }
+                }
+                Some(_) => CstNodeKind::Token(self.bump()),
+            };
+            let trailing = self.trivia(false);
+            nodes.push(CstNode {
+                leading,
+                kind,
+                trailing,
+            });
+        }
+    }
+
+    /// Take the next trivia tokens, stopping before a newline unless
+    /// `newlines` is true.
+    fn trivia(&mut self, newlines: bool) -> Vec<CstToken> {
+        let len = self.tokens[self.pos..]
+            .iter()
+            .take_while(|x| x.token.value.is_trivia())
+            .take_while(|x| newlines || !x.token.value.is_newlines())
+            .count();
+        self.pos += len;
+        self.tokens[self.pos - len..self.pos].to_vec()
+    }
+
+    /// Take the next token.
+    fn bump(&mut self) -> CstToken {
+        self.pos += 1;
+        self.tokens[self.pos - 1].clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SynNode;
+    use proptest::prelude::*;
+
+    fn parse_syn(src: &str, interner: &mut Interner) -> Vec<SynNode> {
+        src.char_indices()
+            .src_code()
+            .lexer(interner)
+            .parse_syn()
+            .parse()
+    }
+
+    #[test]
+    fn test_cst_lossless() {
+        let demo = include_str!("../../../examples/demo.deck");
+        let sources = [
+            demo,
+            "",
+            " \n\t// only trivia\n",
+            "a {\n    b ( c",
+            "a ) b { c } }",
+            "( a } b { c )",
+            "say \"a (b)\" \"\\q\" \"open\r\nx // c ) \"d\r\n",
+        ];
+        let mut interner = Interner::new();
+        for src in sources {
+            let cst = CstParser::parse_src(src, &mut interner);
+            assert_eq!(cst.to_string(), src);
+            assert_eq!(cst.syn_nodes(), parse_syn(src, &mut interner), "{src:?}");
+        }
+    }
+
+    #[test]
+    fn test_cst_trivia() {
+        let src = "// doc\nx {} // x\n\n{\n    y  \n    // end\n}\n";
+        let cst = CstParser::parse_src(src, &mut Interner::new());
+        let texts = |tokens: &[CstToken]| tokens.iter().map(|x| x.to_string()).collect::<String>();
+
+        assert_eq!(cst.nodes.len(), 3);
+        assert_eq!(texts(&cst.nodes[0].leading), "// doc\n");
+        assert_eq!(texts(&cst.nodes[0].trailing), " ");
+        assert_eq!(texts(&cst.nodes[1].leading), "");
+        assert_eq!(texts(&cst.nodes[1].trailing), " // x");
+        assert_eq!(texts(&cst.nodes[2].leading), "\n\n");
+        assert_eq!(texts(&cst.trailing), "\n");
+
+        let CstNodeKind::Brac {
+            children, inner, ..
+        } = &cst.nodes[2].kind
+        else {
+            panic!("expected brackets");
+        };
+        assert_eq!(texts(&children[0].leading), "\n    ");
+        assert_eq!(texts(&children[0].trailing), "  ");
+        assert_eq!(texts(inner), "\n    // end\n");
+        assert_eq!(
+            cst.nodes[2].span().len,
+            src.len() - "// doc\nx {} // x\n\n".len() - 1
+        );
+    }
+
+    #[test]
+    fn test_cst_too_deep() {
+        let nested = "(".repeat(100_000) + "a" + &")".repeat(100_000);
+        let sources = [
+            format!("x {{ {nested} }} // x\n"),
+            format!("x {{ {} a\n\t", "( ".repeat(100_000)),
+        ];
+        let mut interner = Interner::new();
+        for src in sources {
+            let cst = CstParser::parse_src(&src, &mut interner);
+            assert_eq!(cst.to_string(), src);
+            assert_eq!(cst.syn_nodes(), parse_syn(&src, &mut interner));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_cst_matches_syn_parser(src in "[ab(){}\"\\\\/\n\r\t ]{0,48}") {
+            let mut interner = Interner::new();
+            let cst = CstParser::parse_src(&src, &mut interner);
+            prop_assert_eq!(cst.to_string(), src.clone());
+            prop_assert_eq!(cst.syn_nodes(), parse_syn(&src, &mut interner));
+        }
+    }
+}
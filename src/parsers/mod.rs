@@ -2,6 +2,8 @@ pub mod src_code;
 pub use src_code::*;
 pub mod syn;
 pub use syn::*;
+pub mod cst;
+pub use cst::*;
 pub mod sem;
 pub use sem::*;
 pub mod token;
@@ -1,31 +1,57 @@
-use crate::{utils::SimpleDisplay, Spanned};
+use std::sync::Arc;
+
+use crate::{quote_str, utils::SimpleDisplay, Interner, Spanned, Symbol, SynErrorKind};
 
 /// Semantic node kind.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum SemNodeKind {
     /// Definition: mapping from identifiers to an expression block
+    ///
+    /// The body and expressions are shared with the definitions evaluated from them.
     Def {
         idents: Vec<SemNodeExpr>,
-        body: Vec<SemNode>,
-        exprs: Vec<SemNodeExpr>,
+        body: Arc<[SemNode]>,
+        exprs: Arc<[SemNodeExpr]>,
     },
 
     /// Error: an error occurred
-    Error { msg: String, children: Vec<SemNode> },
+    Error {
+        kind: SemErrorKind,
+        children: Vec<SemNode>,
+    },
+}
+
+/// Semantic error kind.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, thiserror::Error)]
+pub enum SemErrorKind {
+    /// Syntactic error: an error from syntactic parsing
+    #[error(transparent)]
+    Syn(SynErrorKind),
+
+    /// Missing body: expressions that are not followed by a definition body
+    #[error("Expected a definition body after expressions")]
+    MissingBody,
+
+    /// Unexpected body: a definition body inside an expression
+    #[error("Unexpected definition body in expression")]
+    UnexpectedBody,
 }
 
 /// Semantic node expression kind.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum SemNodeExprKind {
     /// Identifier: an identifier
-    Ident(String),
+    Ident(Symbol),
+
+    /// String: a string literal
+    Str(Arc<str>),
 
     /// Inner: an inner expression block
     Inner(Vec<SemNodeExpr>),
 
     /// Error: an error occurred
     Error {
-        msg: String,
+        kind: SemErrorKind,
         children: Vec<SemNodeExpr>,
     },
 }
@@ -34,7 +60,7 @@ pub enum SemNodeExprKind {
 pub type SemNodeExpr = Spanned<SemNodeExprKind>;
 
 impl SimpleDisplay for SemNodeExpr {
-    fn simple_display(&self) -> String {
+    fn simple_display(&self, interner: &Interner) -> String {
         fn indent(lines: String) -> String {
             lines
                 .lines()
@@ -46,33 +72,38 @@ impl SimpleDisplay for SemNodeExpr {
             format!("ident: {}", ident)
         }
 
-        fn format_inner(inner: &Vec<SemNodeExpr>) -> String {
+        fn format_inner(inner: &Vec<SemNodeExpr>, interner: &Interner) -> String {
             format!(
                 "inner: [\n{}]",
                 inner
                     .iter()
-                    .map(|x| format!("{},", x.simple_display()))
+                    .map(|x| format!("{},", x.simple_display(interner)))
                     .map(indent)
                     .collect::<String>()
             )
         }
 
-        fn format_error(msg: &str, children: &Vec<SemNodeExpr>) -> String {
+        fn format_error(
+            msg: &SemErrorKind,
+            children: &Vec<SemNodeExpr>,
+            interner: &Interner,
+        ) -> String {
             format!(
                 "error: '{}', [\n{}]",
                 msg,
                 children
                     .iter()
-                    .map(|x| format!("{},", x.simple_display()))
+                    .map(|x| format!("{},", x.simple_display(interner)))
                     .map(indent)
                     .collect::<String>()
             )
         }
 
         match &self.value {
-            SemNodeExprKind::Ident(ident) => format_ident(ident),
-            SemNodeExprKind::Inner(inner) => format_inner(inner),
-            SemNodeExprKind::Error { msg, children } => format_error(msg, children),
+            SemNodeExprKind::Ident(ident) => format_ident(interner.resolve(*ident)),
+            SemNodeExprKind::Str(value) => format!("str: {}", quote_str(value)),
+            SemNodeExprKind::Inner(inner) => format_inner(inner, interner),
+            SemNodeExprKind::Error { kind, children } => format_error(kind, children, interner),
         }
     }
 }
@@ -81,7 +112,7 @@ impl SimpleDisplay for SemNodeExpr {
 pub type SemNode = Spanned<SemNodeKind>;
 
 impl SimpleDisplay for SemNode {
-    fn simple_display(&self) -> String {
+    fn simple_display(&self, interner: &Interner) -> String {
         fn indent(lines: String) -> String {
             lines
                 .lines()
@@ -89,40 +120,40 @@ impl SimpleDisplay for SemNode {
                 .collect::<String>()
         }
 
-        fn format_idents(idents: &[SemNodeExpr]) -> String {
+        fn format_idents(idents: &[SemNodeExpr], interner: &Interner) -> String {
             match idents {
                 [] => "idents: []".to_string(),
                 idents => format!(
                     "idents: [\n{}]",
                     idents
                         .iter()
-                        .map(|x| format!("{},\n", x.simple_display()))
+                        .map(|x| format!("{},\n", x.simple_display(interner)))
                         .map(indent)
                         .collect::<String>()
                 ),
             }
         }
 
-        fn format_body(body: &[SemNode]) -> String {
+        fn format_body(body: &[SemNode], interner: &Interner) -> String {
             match body {
                 [] => "body: []".to_string(),
                 body => format!(
                     "body: [\n{}]",
                     body.iter()
-                        .map(|x| format!("{},\n", x.simple_display()))
+                        .map(|x| format!("{},\n", x.simple_display(interner)))
                         .map(indent)
                         .collect::<String>()
                 ),
             }
         }
 
-        fn format_expr(expr: &[SemNodeExpr]) -> String {
+        fn format_expr(expr: &[SemNodeExpr], interner: &Interner) -> String {
             match expr {
                 [] => "expr: []".to_string(),
                 expr => format!(
                     "expr: [\n{}]",
                     expr.iter()
-                        .map(|x| format!("{},\n", x.simple_display()))
+                        .map(|x| format!("{},\n", x.simple_display(interner)))
                         .map(indent)
                         .collect::<String>()
                 ),
@@ -139,13 +170,16 @@ impl SimpleDisplay for SemNode {
                     "Def {{\n{}}}",
                     indent(format!(
                         "{}\n{}\n{}\n",
-                        format_idents(idents),
-                        format_body(body),
-                        format_expr(expr),
+                        format_idents(idents, interner),
+                        format_body(body, interner),
+                        format_expr(expr, interner),
                     )),
                 )
             }
-            SemNodeKind::Error { msg, children } => {
+            SemNodeKind::Error {
+                kind: msg,
+                children,
+            } => {
                 format!(
                     "Error {{\n{}}}",
                     indent(format!(
@@ -153,7 +187,7 @@ impl SimpleDisplay for SemNode {
                         msg,
                         children
                             .iter()
-                            .map(|x| format!("{},\n", x.simple_display()))
+                            .map(|x| format!("{},\n", x.simple_display(interner)))
                             .map(indent)
                             .collect::<String>()
                     )),
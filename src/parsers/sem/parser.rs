@@ -1,5 +1,5 @@
 use super::*;
-use crate::{utils::AdvanceIterExt, EvalDebugOption, Evaluator, SynNode, SynNodeKind};
+use crate::{EvalDebugOption, EvalError, Evaluator, Interner, SynNode, SynNodeKind};
 
 /// Semantic parser result.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -9,184 +9,490 @@ pub struct SemParserResult<Item> {
 }
 
 /// Semantic parser.
+///
+/// Nodes are parsed in one forward pass over slices, so each node is
+/// visited once.
 #[derive(Debug, Clone)]
-pub struct SemParser<Iter>
-where
-    Iter: Iterator<Item = SynNode> + std::fmt::Debug + Clone + AdvanceIterExt,
-{
-    iter: Iter,
+pub struct SemParser {
+    nodes: Vec<SynNode>,
+    pos: usize,
 }
 
-impl<Iter> SemParser<Iter>
-where
-    Iter: Iterator<Item = SynNode> + std::fmt::Debug + Clone + AdvanceIterExt,
-{
+impl SemParser {
     /// Create a new semantic parser.
-    pub fn new(iter: Iter) -> Self {
-        Self { iter }
+    pub fn new(nodes: Vec<SynNode>) -> Self {
+        Self { nodes, pos: 0 }
     }
 
-    /// Evaluate.
-    pub fn evaluate(self) {
+    /// Evaluate, with the interner the symbols of the nodes come from.
+    pub fn evaluate(self, interner: Interner) -> Result<(), EvalError> {
         let nodes = self.collect::<Vec<_>>();
-        Evaluator::new(nodes.iter()).for_each(drop);
+        Evaluator::new(nodes, interner).collect()
     }
 
     /// Evaluate with debug options.
-    pub fn evaluate_with_debug(self, debug_options: EvalDebugOption) {
+    pub fn evaluate_with_debug(
+        self,
+        debug_options: EvalDebugOption,
+        interner: Interner,
+    ) -> Result<(), EvalError> {
         let nodes = self.collect::<Vec<_>>();
-        Evaluator::new_with_debug(nodes.iter(), debug_options).for_each(drop);
+        Evaluator::new_with_debug(nodes, debug_options, interner).collect()
     }
 
     /// Parse an expression.
     pub fn parse_expr(&self) -> Option<SemParserResult<SemNodeExpr>> {
-        let mut iter = self.iter.clone();
-        let node = iter.next()?;
-        match &node.value {
-            SynNodeKind::Ident(idents) => {
-                let idents = idents.clone();
-                Some(SemParserResult {
-                    item: SemNodeExpr {
-                        value: SemNodeExprKind::Ident(idents),
-                        span: node.span,
-                    },
-                    advance: 1,
-                })
+        parse_expr(self.rest())
+    }
+
+    /// Parse a vec of expression.
+    pub fn parse_expr_vec(&self) -> Option<SemParserResult<Vec<SemNodeExpr>>> {
+        Some(parse_expr_vec(self.rest()))
+    }
+
+    /// Parse a definition.
+    pub fn parse_def(&self) -> Option<SemParserResult<SemNode>> {
+        let nodes = self.rest();
+        let idents = parse_expr_vec(nodes);
+        let body = nodes.get(idents.advance).filter(|x| is_def_body(x))?;
+        Some(SemParserResult {
+            item: parse_def(idents.item, body),
+            advance: idents.advance + 1,
+        })
+    }
+
+    /// Parse a vec of definition.
+    pub fn parse_def_vec(&self) -> Option<SemParserResult<Vec<SemNode>>> {
+        Some(parse_body(self.rest()).0)
+    }
+
+    /// Get the nodes that are not parsed yet.
+    fn rest(&self) -> &[SynNode] {
+        &self.nodes[self.pos..]
+    }
+}
+
+impl Iterator for SemParser {
+    type Item = SemNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nodes = self.rest();
+        let exprs = parse_expr_vec(nodes);
+        if let Some(body) = nodes.get(exprs.advance).filter(|x| is_def_body(x)) {
+            let def = parse_def(exprs.item, body);
+            self.pos += exprs.advance + 1;
+            return Some(def);
+        }
+
+        // Expressions at the end without a definition body
+        let exprs = exprs.item;
+        let first = exprs.first()?;
+        if let SemNodeExprKind::Error { kind, .. } = &first.value {
+            self.pos += 1;
+            return Some(SemNode {
+                value: SemNodeKind::Error {
+                    kind: kind.clone(),
+                    children: vec![],
+                },
+                span: first.span.clone(),
+            });
+        }
+
+        let len = exprs
+            .iter()
+            .take_while(|x| !matches!(x.value, SemNodeExprKind::Error { .. }))
+            .count();
+        self.pos += len;
+        Some(SemNode {
+            value: SemNodeKind::Error {
+                kind: SemErrorKind::MissingBody,
+                children: vec![],
+            },
+            span: first.span.to(&exprs[len - 1].span),
+        })
+    }
+}
+
+/// Parse an expression.
+fn parse_expr(nodes: &[SynNode]) -> Option<SemParserResult<SemNodeExpr>> {
+    let node = nodes.first()?;
+    let value = match &node.value {
+        SynNodeKind::Ident(ident) => SemNodeExprKind::Ident(*ident),
+        SynNodeKind::Str(value) => SemNodeExprKind::Str(value.clone()),
+        SynNodeKind::Brac {
+            open: '(',
+            close: ')',
+            children,
+        } => {
+            let exprs = parse_expr_vec(children);
+            if exprs.advance < children.len() {
+                SemNodeExprKind::Error {
+                    kind: SemErrorKind::UnexpectedBody,
+                    children: exprs.item,
+                }
+            } else {
+                SemNodeExprKind::Inner(exprs.item)
+            }
+        }
+        // Errors in curly brackets are parsed as definition bodies
+        SynNodeKind::Error { kind, children } if kind.open() != Some('{') => {
+            SemNodeExprKind::Error {
+                kind: SemErrorKind::Syn(kind.clone()),
+                children: parse_expr_vec(children).item,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(SemParserResult {
+        item: SemNodeExpr {
+            value,
+            span: node.span.clone(),
+        },
+        advance: 1,
+    })
+}
+
+/// Parse a vec of expression.
+fn parse_expr_vec(nodes: &[SynNode]) -> SemParserResult<Vec<SemNodeExpr>> {
+    let mut advance = 0;
+    let mut exprs = vec![];
+    while let Some(expr) = parse_expr(&nodes[advance..]) {
+        exprs.push(expr.item);
+        advance += expr.advance;
+    }
+    SemParserResult {
+        item: exprs,
+        advance,
+    }
+}
+
+/// Check if a node is the body of a definition.
+fn is_def_body(node: &SynNode) -> bool {
+    matches!(
+        node.value,
+        SynNodeKind::Brac {
+            open: '{',
+            close: '}',
+            ..
+        } | SynNodeKind::Error { .. }
+    )
+}
+
+/// Parse a definition from its identifiers and its body node.
+fn parse_def(idents: Vec<SemNodeExpr>, body: &SynNode) -> SemNode {
+    let value = match &body.value {
+        SynNodeKind::Error { kind, children } => SemNodeKind::Error {
+            kind: SemErrorKind::Syn(kind.clone()),
+            children: parse_body(children).0.item,
+        },
+        SynNodeKind::Brac { children, .. } => {
+            let (body, exprs) = parse_body(children);
+            SemNodeKind::Def {
+                idents,
+                body: body.item.into(),
+                exprs: exprs.item.into(),
             }
-            SynNodeKind::Brac {
-                open: '(',
-                close: ')',
-                children,
-            } => {
-                let children = children.clone();
-                let parser = SemParser::new(children.into_iter());
-                let exprs = parser.parse_expr_vec()?;
-                Some(SemParserResult {
+        }
+        SynNodeKind::Ident(_) | SynNodeKind::Str(_) => {
+            unreachable!("identifiers are not definition bodies")
+        }
+    };
+
+    SemNode {
+        value,
+        span: body.span.clone(),
+    }
+}
+
+/// Parse definitions followed by the expressions that end a body.
+fn parse_body(
+    nodes: &[SynNode],
+) -> (
+    SemParserResult<Vec<SemNode>>,
+    SemParserResult<Vec<SemNodeExpr>>,
+) {
+    let mut advance = 0;
+    let mut defs = vec![];
+    loop {
+        let exprs = parse_expr_vec(&nodes[advance..]);
+        match nodes
+            .get(advance + exprs.advance)
+            .filter(|x| is_def_body(x))
+        {
+            Some(body) => {
+                defs.push(parse_def(exprs.item, body));
+                advance += exprs.advance + 1;
+            }
+            None => {
+                let defs = SemParserResult {
+                    item: defs,
+                    advance,
+                };
+                return (defs, exprs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{utils::AdvanceIterExt, SrcCodeIterExt};
+    use proptest::prelude::*;
+
+    /// Previous parser, cloning the remaining nodes at each step.
+    #[derive(Debug, Clone)]
+    struct Reference<Iter>
+    where
+        Iter: Iterator<Item = SynNode> + std::fmt::Debug + Clone + AdvanceIterExt,
+    {
+        iter: Iter,
+    }
+
+    impl<Iter> Reference<Iter>
+    where
+        Iter: Iterator<Item = SynNode> + std::fmt::Debug + Clone + AdvanceIterExt,
+    {
+        fn new(iter: Iter) -> Self {
+            Self { iter }
+        }
+
+        /// Parse an expression.
+        fn parse_expr(&self) -> Option<SemParserResult<SemNodeExpr>> {
+            let mut iter = self.iter.clone();
+            let node = iter.next()?;
+            match &node.value {
+                SynNodeKind::Ident(idents) => {
+                    let idents = *idents;
+                    Some(SemParserResult {
+                        item: SemNodeExpr {
+                            value: SemNodeExprKind::Ident(idents),
+                            span: node.span,
+                        },
+                        advance: 1,
+                    })
+                }
+                SynNodeKind::Str(value) => Some(SemParserResult {
                     item: SemNodeExpr {
-                        value: SemNodeExprKind::Inner(exprs.item),
+                        value: SemNodeExprKind::Str(value.clone()),
                         span: node.span,
                     },
                     advance: 1,
-                })
+                }),
+                SynNodeKind::Brac {
+                    open: '(',
+                    close: ')',
+                    children,
+                } => {
+                    let len = children.len();
+                    let children = children.clone();
+                    let parser = Reference::new(children.into_iter());
+                    let exprs = parser.parse_expr_vec()?;
+                    let value = if exprs.advance < len {
+                        SemNodeExprKind::Error {
+                            kind: SemErrorKind::UnexpectedBody,
+                            children: exprs.item,
+                        }
+                    } else {
+                        SemNodeExprKind::Inner(exprs.item)
+                    };
+                    Some(SemParserResult {
+                        item: SemNodeExpr {
+                            value,
+                            span: node.span,
+                        },
+                        advance: 1,
+                    })
+                }
+                // Errors in curly brackets are parsed as definition bodies
+                SynNodeKind::Error { kind, children } if kind.open() != Some('{') => {
+                    let children = children.clone();
+                    let parser = Reference::new(children.into_iter());
+                    let exprs = parser.parse_expr_vec()?;
+                    Some(SemParserResult {
+                        item: SemNodeExpr {
+                            value: SemNodeExprKind::Error {
+                                kind: SemErrorKind::Syn(kind.clone()),
+                                children: exprs.item,
+                            },
+                            span: node.span,
+                        },
+                        advance: 1,
+                    })
+                }
+                _ => None,
             }
-            SynNodeKind::Error { msg, children } => {
-                let children = children.clone();
-                let parser = SemParser::new(children.into_iter());
-                let expr = parser.parse_expr();
-                Some(SemParserResult {
-                    item: SemNodeExpr {
-                        value: SemNodeExprKind::Error {
-                            msg: msg.clone(),
-                            children: expr.map_or(vec![], |x| vec![x.item]),
+        }
+
+        /// Parse a vec of expression.
+        fn parse_expr_vec(&self) -> Option<SemParserResult<Vec<SemNodeExpr>>> {
+            let mut advance = 0;
+            let mut iter = self.iter.clone();
+            let mut exprs = vec![];
+            while let Some(expr) = Reference::new(iter.clone()).parse_expr() {
+                exprs.push(expr.item);
+                iter.advance(expr.advance);
+                advance += expr.advance;
+            }
+            Some(SemParserResult {
+                item: exprs,
+                advance,
+            })
+        }
+
+        /// Parse a definition.
+        fn parse_def(&self) -> Option<SemParserResult<SemNode>> {
+            let mut advance = 0;
+            let mut iter = self.iter.clone();
+            let idents = Reference::new(iter.clone()).parse_expr_vec()?;
+            iter.advance(idents.advance);
+            advance += idents.advance;
+
+            match iter.next()? {
+                SynNode {
+                    value:
+                        SynNodeKind::Brac {
+                            open: '{',
+                            close: '}',
+                            children,
                         },
-                        span: node.span,
-                    },
-                    advance: 1,
-                })
+                    span,
+                } => {
+                    let mut children_iter = children.into_iter();
+                    let body = Reference::new(children_iter.clone()).parse_def_vec()?;
+                    children_iter.advance(body.advance);
+                    let expr = Reference::new(children_iter.clone()).parse_expr_vec()?;
+                    children_iter.advance(expr.advance);
+                    Some(SemParserResult {
+                        item: SemNode {
+                            value: SemNodeKind::Def {
+                                idents: idents.item,
+                                body: body.item.into(),
+                                exprs: expr.item.into(),
+                            },
+                            span,
+                        },
+                        advance: advance + 1,
+                    })
+                }
+                SynNode {
+                    value: SynNodeKind::Error { kind, children },
+                    span,
+                } => {
+                    let parser = Reference::new(children.into_iter());
+                    let defs = parser.parse_def_vec()?;
+                    Some(SemParserResult {
+                        item: SemNode {
+                            value: SemNodeKind::Error {
+                                kind: SemErrorKind::Syn(kind),
+                                children: defs.item,
+                            },
+                            span,
+                        },
+                        advance: advance + 1,
+                    })
+                }
+                _ => None,
             }
-            _ => None,
         }
-    }
 
-    /// Parse a vec of expression.
-    pub fn parse_expr_vec(&self) -> Option<SemParserResult<Vec<SemNodeExpr>>> {
-        let mut advance = 0;
-        let mut iter = self.iter.clone();
-        let mut exprs = vec![];
-        while let Some(expr) = SemParser::new(iter.clone()).parse_expr() {
-            exprs.push(expr.item);
-            iter.advance(expr.advance);
-            advance += expr.advance;
+        /// Parse a vec of definition.
+        fn parse_def_vec(&self) -> Option<SemParserResult<Vec<SemNode>>> {
+            let mut advance = 0;
+            let mut iter = self.iter.clone();
+            let mut defs = vec![];
+            while let Some(def) = Reference::new(iter.clone()).parse_def() {
+                defs.push(def.item);
+                iter.advance(def.advance);
+                advance += def.advance;
+            }
+            Some(SemParserResult {
+                item: defs,
+                advance,
+            })
         }
-        Some(SemParserResult {
-            item: exprs,
-            advance,
-        })
     }
 
-    /// Parse a definition.
-    pub fn parse_def(&self) -> Option<SemParserResult<SemNode>> {
-        let mut advance = 0;
-        let mut iter = self.iter.clone();
-        let idents = SemParser::new(iter.clone()).parse_expr_vec()?;
-        iter.advance(idents.advance);
-        advance += idents.advance;
-
-        match iter.next()? {
-            SynNode {
-                value:
-                    SynNodeKind::Brac {
-                        open: '{',
-                        close: '}',
-                        children,
-                    },
-                span,
-            } => {
-                let mut children_iter = children.into_iter();
-                let body = SemParser::new(children_iter.clone()).parse_def_vec()?;
-                children_iter.advance(body.advance);
-                let expr = SemParser::new(children_iter.clone()).parse_expr_vec()?;
-                children_iter.advance(expr.advance);
-                Some(SemParserResult {
-                    item: SemNode {
-                        value: SemNodeKind::Def {
-                            idents: idents.item,
-                            body: body.item,
-                            exprs: expr.item,
-                        },
-                        span,
-                    },
-                    advance: advance + 1,
-                })
+    impl<Iter> Iterator for Reference<Iter>
+    where
+        Iter: Iterator<Item = SynNode> + std::fmt::Debug + Clone + AdvanceIterExt,
+    {
+        type Item = SemNode;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if let Some(def) = self.parse_def() {
+                self.iter.advance(def.advance);
+                return Some(def.item);
             }
-            SynNode {
-                value: SynNodeKind::Error { msg, children },
-                span,
-            } => {
-                let children = children.clone();
-                let parser = SemParser::new(children.into_iter());
-                let def = parser.parse_def();
-                Some(SemParserResult {
-                    item: SemNode {
-                        value: SemNodeKind::Error {
-                            msg: msg.clone(),
-                            children: def.map_or(vec![], |x| vec![x.item]),
-                        },
-                        span,
+
+            // Expressions at the end without a definition body
+            let exprs = self.parse_expr_vec()?.item;
+            let first = exprs.first()?;
+            if let SemNodeExprKind::Error { kind, .. } = &first.value {
+                self.iter.advance(1);
+                return Some(SemNode {
+                    value: SemNodeKind::Error {
+                        kind: kind.clone(),
+                        children: vec![],
                     },
-                    advance: advance + 1,
-                })
+                    span: first.span.clone(),
+                });
             }
-            _ => None,
+
+            let len = exprs
+                .iter()
+                .take_while(|x| !matches!(x.value, SemNodeExprKind::Error { .. }))
+                .count();
+            self.iter.advance(len);
+            Some(SemNode {
+                value: SemNodeKind::Error {
+                    kind: SemErrorKind::MissingBody,
+                    children: vec![],
+                },
+                span: first.span.to(&exprs[len - 1].span),
+            })
         }
     }
 
-    /// Parse a vec of definition.
-    pub fn parse_def_vec(&self) -> Option<SemParserResult<Vec<SemNode>>> {
-        let mut advance = 0;
-        let mut iter = self.iter.clone();
-        let mut defs = vec![];
-        while let Some(def) = SemParser::new(iter.clone()).parse_def() {
-            defs.push(def.item);
-            iter.advance(def.advance);
-            advance += def.advance;
-        }
-        Some(SemParserResult {
-            item: defs,
-            advance,
-        })
+    fn assert_same_as_reference(src: &str) {
+        let syn_nodes = src
+            .char_indices()
+            .src_code()
+            .lexer(&mut Interner::new())
+            .parse_syn()
+            .parse();
+        let expected = Reference::new(syn_nodes.clone().into_iter()).collect::<Vec<_>>();
+        assert_eq!(
+            SemParser::new(syn_nodes).collect::<Vec<_>>(),
+            expected,
+            "{src:?}"
+        );
     }
-}
 
-impl<Iter> Iterator for SemParser<Iter>
-where
-    Iter: Iterator<Item = SynNode> + std::fmt::Debug + Clone + AdvanceIterExt,
-{
-    type Item = SemNode;
+    #[test]
+    fn test_sem_parser_matches_reference() {
+        for src in [
+            include_str!("../../../examples/demo.deck"),
+            "",
+            "{ comment }",
+            "a { b { c } d { (e f) } g }",
+            "1 {}\na { b ( }\nc ) d {}\n( x {} ) e {}\nf g\n}",
+            "a b",
+            "a { b",
+            "( a { b }",
+            "a { b ) c } d",
+            "a { ( b } c",
+            "a \"b\" { \"c\" ( \"d\" ) }\n\"e\" {}",
+        ] {
+            assert_same_as_reference(src);
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let def = self.parse_def()?;
-        self.iter.advance(def.advance);
-        Some(def.item)
+    proptest! {
+        #[test]
+        fn test_sem_parser_matches_reference_generated(src in "[ab\"(){}\n ]{0,48}") {
+            assert_same_as_reference(&src);
+        }
     }
 }
@@ -2,5 +2,6 @@ mod spanned;
 pub use spanned::*;
 mod src_code_iter;
 pub use src_code_iter::*;
+#[allow(clippy::module_inception)]
 mod src_code;
 pub use src_code::*;
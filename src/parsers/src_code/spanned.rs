@@ -1,3 +1,4 @@
+#[allow(unused_imports)]
 use super::*;
 
 /// Span position in source code.
@@ -8,18 +9,58 @@ pub struct SpanPos {
     pub idx: usize,
 }
 
+/// Source identifier, telling apart the sources spans point into.
+///
+/// The default identifier is the one of a lone source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default, PartialOrd, Ord)]
+pub struct SrcId(pub usize);
+
 /// Span of source code.
 /// Used to store location of tokens and errors in source code.
+///
+/// `len` is the length in bytes.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Span {
+    pub src: SrcId,
     pub start: SpanPos,
     pub len: usize,
 }
 
 impl Span {
-    /// Create a new span.
+    /// Create a new span in the default source.
     pub fn new(start: SpanPos, len: usize) -> Self {
-        Self { start, len }
+        Self {
+            src: SrcId::default(),
+            start,
+            len,
+        }
+    }
+
+    /// Create a span covering all of `src`.
+    pub fn of_src(src: &str) -> Self {
+        Self {
+            src: SrcId::default(),
+            start: SpanPos {
+                line: 1,
+                col: 1,
+                idx: 0,
+            },
+            len: src.len(),
+        }
+    }
+
+    /// Create a span from the start of this span to the end of `other`.
+    pub fn to(&self, other: &Span) -> Self {
+        Self {
+            src: self.src,
+            start: self.start.clone(),
+            len: other.end_idx().saturating_sub(self.start.idx),
+        }
+    }
+
+    /// Get the byte index after the end of the span.
+    pub fn end_idx(&self) -> usize {
+        self.start.idx + self.len
     }
 }
 
@@ -1,11 +1,12 @@
 use super::*;
 use crate::utils::NextRangePeekExt;
-use crate::Lexer;
+use crate::{Interner, Lexer};
 
 /// Source code iterator.
 #[derive(Debug, Clone)]
 pub struct SrcCodeIter<'a> {
     iter: std::str::CharIndices<'a>,
+    src: SrcId,
     curr_line: usize,
     curr_col: usize,
 }
@@ -15,14 +16,21 @@ impl<'a> SrcCodeIter<'a> {
     pub fn new(iter: std::str::CharIndices<'a>) -> Self {
         Self {
             iter,
+            src: SrcId::default(),
             curr_line: 1,
             curr_col: 1,
         }
     }
 
-    /// Create a lexer.
-    pub fn lexer(self) -> Lexer<Self> {
-        Lexer::new(self.next_range_peek())
+    /// Set the source identifier of the spans.
+    pub fn with_id(mut self, src: SrcId) -> Self {
+        self.src = src;
+        self
+    }
+
+    /// Create a lexer, interning identifiers into `interner`.
+    pub fn lexer(self, interner: &mut Interner) -> Lexer<'_, Self> {
+        Lexer::new(self.next_range_peek(), interner)
     }
 }
 
@@ -47,7 +55,11 @@ impl<'a> Iterator for SrcCodeIter<'a> {
 
                 Some(SrcCode {
                     value: ch,
-                    span: Span::new(pos, 1),
+                    span: Span {
+                        src: self.src,
+                        start: pos,
+                        len: ch.len_utf8(),
+                    },
                 })
             }
             None => None,
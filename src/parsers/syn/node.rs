@@ -1,10 +1,15 @@
-use crate::Spanned;
+use std::sync::Arc;
+
+use crate::{quote_str, Interner, Spanned, Symbol, TokenErrorKind};
 
 /// Syntactic node kind.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum SynNodeKind {
     /// Identifier: an identifiers
-    Ident(String),
+    Ident(Symbol),
+
+    /// String: a string literal
+    Str(Arc<str>),
 
     /// Brackets: a node enclosed in brackets
     Brac {
@@ -14,15 +19,66 @@ pub enum SynNodeKind {
     },
 
     /// Error: an error node
-    Error { msg: String, children: Vec<SynNode> },
+    Error {
+        kind: SynErrorKind,
+        children: Vec<SynNode>,
+    },
+}
+
+/// Syntactic error kind.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, thiserror::Error)]
+pub enum SynErrorKind {
+    /// Mismatched brackets: a closing bracket that does not match the opening one
+    #[error("Mismatched brackets: '{}' and '{}'", .open.value, .close.value)]
+    MismatchedBrac {
+        open: Spanned<char>,
+        close: Spanned<char>,
+    },
+
+    /// Unclosed bracket: an opening bracket without a closing one
+    #[error(
+        "Unclosed bracket '{}' opened at {}:{}",
+        .open.value,
+        .open.span.start.line,
+        .open.span.start.col
+    )]
+    UnclosedBrac { open: Spanned<char> },
+
+    /// Unexpected closing bracket: a closing bracket without an opening one
+    #[error("Unexpected closing bracket '{}'", .close.value)]
+    UnexpectedCloseBrac { close: Spanned<char> },
+
+    /// Too deep: brackets nested more than [`crate::SynParser::MAX_DEPTH`] levels
+    #[error(
+        "Brackets nested too deep: more than {} levels",
+        crate::SynParser::MAX_DEPTH
+    )]
+    TooDeep { open: Spanned<char> },
+
+    /// Token error: characters that do not form a token
+    #[error(transparent)]
+    Token(TokenErrorKind),
+}
+
+impl SynErrorKind {
+    /// Get the opening bracket of the error node, if any.
+    pub fn open(&self) -> Option<char> {
+        match self {
+            SynErrorKind::MismatchedBrac { open, .. }
+            | SynErrorKind::UnclosedBrac { open }
+            | SynErrorKind::TooDeep { open } => Some(open.value),
+            SynErrorKind::UnexpectedCloseBrac { .. } | SynErrorKind::Token(_) => None,
+        }
+    }
 }
 
 /// Syntactic node.
 pub type SynNode = Spanned<SynNodeKind>;
 
 impl SynNode {
-    /// Create a simple display string.
-    pub fn simple_display(&self) -> String {
+    /// Create a simple display string, with symbols resolved through `interner`.
+    pub fn simple_display(&self, interner: &Interner) -> String {
         fn indent(lines: String) -> String {
             lines
                 .lines()
@@ -32,7 +88,10 @@ impl SynNode {
 
         match &self.value {
             SynNodeKind::Ident(ident) => {
-                format!("Ident '{}'", ident)
+                format!("Ident '{}'", interner.resolve(*ident))
+            }
+            SynNodeKind::Str(value) => {
+                format!("Str {}", quote_str(value))
             }
             SynNodeKind::Brac {
                 open,
@@ -44,18 +103,18 @@ impl SynNode {
                     "Brac '{open}{close}' [\n{}]",
                     nodes
                         .iter()
-                        .map(|x| format!("{},\n", x.simple_display()))
+                        .map(|x| format!("{},\n", x.simple_display(interner)))
                         .map(indent)
                         .collect::<String>()
                 ),
             },
-            SynNodeKind::Error { msg, children } => match &children[..] {
-                [] => format!("Error '{msg}' []"),
+            SynNodeKind::Error { kind, children } => match &children[..] {
+                [] => format!("Error '{kind}' []"),
                 nodes => format!(
-                    "Error '{msg}' [\n{}]",
+                    "Error '{kind}' [\n{}]",
                     nodes
                         .iter()
-                        .map(|x| format!("{},\n", x.simple_display()))
+                        .map(|x| format!("{},\n", x.simple_display(interner)))
                         .map(indent)
                         .collect::<String>()
                 ),
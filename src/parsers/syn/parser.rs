@@ -7,7 +7,7 @@ use paste::paste;
 use std::marker::PhantomData;
 
 use super::*;
-use crate::{SemParser, Span, Token, TokenKind};
+use crate::{SemParser, Spanned, Token, TokenKind};
 
 /// Syntactic parser.
 #[derive(Debug, Clone)]
@@ -16,21 +16,63 @@ pub struct SynParser {
 }
 
 impl SynParser {
+    /// Maximum number of brackets nested inside each other, deeper brackets
+    /// are parsed into [`SynErrorKind::TooDeep`] nodes.
+    ///
+    /// Low enough for the recursive passes over the nodes to not overflow
+    /// a 2 MiB stack.
+    pub const MAX_DEPTH: usize = 256;
+
     /// Create a new syntactic parser.
     pub fn new(tokens: Vec<Token>) -> Self {
         Self { tokens }
     }
 
     /// Parse the program.
+    ///
+    /// All tokens are consumed, bracket errors are parsed into
+    /// [`SynNodeKind::Error`] nodes.
     pub fn parse<'a>(&'a self) -> Vec<SynNode> {
         ProgramParser::<'a>::with_tokens(&self.tokens)
             .next()
             .map_or(Vec::new(), |x| x.item)
     }
 
+    /// Check if the tokens end before all brackets are closed.
+    ///
+    /// Only unclosed brackets count, more tokens cannot fix other errors
+    /// inside them.
+    pub fn is_incomplete(&self) -> bool {
+        fn has_other_errors(node: &SynNode) -> bool {
+            match &node.value {
+                SynNodeKind::Ident(_) | SynNodeKind::Str(_) => false,
+                SynNodeKind::Brac { children, .. } => children.iter().any(has_other_errors),
+                SynNodeKind::Error {
+                    kind: SynErrorKind::UnclosedBrac { .. },
+                    children,
+                } => children.iter().any(has_other_errors),
+                SynNodeKind::Error { .. } => true,
+            }
+        }
+
+        match self.parse().last() {
+            Some(
+                node @ SynNode {
+                    value:
+                        SynNodeKind::Error {
+                            kind: SynErrorKind::UnclosedBrac { .. },
+                            ..
+                        },
+                    ..
+                },
+            ) => !has_other_errors(node),
+            _ => false,
+        }
+    }
+
     /// Parse semantic.
-    pub fn parse_sem(&self) -> SemParser<std::vec::IntoIter<SynNode>> {
-        SemParser::new(self.parse().into_iter())
+    pub fn parse_sem(&self) -> SemParser {
+        SemParser::new(self.parse())
     }
 }
 
@@ -45,6 +87,14 @@ struct SynParserResult<'a, Item> {
 trait SynParserIter<'a, Item>: Iterator<Item = SynParserResult<'a, Item>> {
     /// Supply the tokens to the parser.
     fn with_tokens(tokens: &'a [Token]) -> Self;
+
+    /// Supply the tokens to the parser, inside `depth` brackets.
+    fn with_depth(tokens: &'a [Token], _depth: usize) -> Self
+    where
+        Self: Sized,
+    {
+        Self::with_tokens(tokens)
+    }
 }
 
 macro_rules! token_parser {
@@ -84,8 +134,8 @@ macro_rules! token_parser {
 token_parser!(OpenBrac, OpenBrac(_));
 token_parser!(CloseBrac, CloseBrac(_));
 token_parser!(Ident, Ident(_));
-token_parser!(Spaces, Spaces);
-token_parser!(Newlines, Newlines);
+token_parser!(Str, Str(_));
+token_parser!(Error, Error(_));
 
 /// Many parser
 #[derive(Debug, Clone)]
@@ -94,6 +144,7 @@ where
     Parser: SynParserIter<'a, Item>,
 {
     tokens: &'a [Token],
+    depth: usize,
     parser: PhantomData<Parser>,
     item: PhantomData<Item>,
 }
@@ -103,8 +154,13 @@ where
     Parser: SynParserIter<'a, Item>,
 {
     fn with_tokens(tokens: &'a [Token]) -> Self {
+        Self::with_depth(tokens, 0)
+    }
+
+    fn with_depth(tokens: &'a [Token], depth: usize) -> Self {
         Self {
             tokens,
+            depth,
             parser: PhantomData,
             item: PhantomData,
         }
@@ -121,7 +177,7 @@ where
         let mut v = Vec::new();
         let mut count = 0;
 
-        while let Some(x) = Parser::with_tokens(&self.tokens[count..]).next() {
+        while let Some(x) = Parser::with_depth(&self.tokens[count..], self.depth).next() {
             v.push(x.item);
             count += x.tokens.len();
         }
@@ -133,53 +189,6 @@ where
     }
 }
 
-/// Many1 parser
-#[derive(Debug, Clone)]
-struct Many1Parser<'a, Parser, Item>
-where
-    Parser: SynParserIter<'a, Item>,
-{
-    tokens: &'a [Token],
-    parser: PhantomData<Parser>,
-    item: PhantomData<Item>,
-}
-
-impl<'a, Parser, Item> SynParserIter<'a, Vec<Item>> for Many1Parser<'a, Parser, Item>
-where
-    Parser: SynParserIter<'a, Item>,
-{
-    fn with_tokens(tokens: &'a [Token]) -> Self {
-        Self {
-            tokens,
-            parser: PhantomData,
-            item: PhantomData,
-        }
-    }
-}
-
-impl<'a, Parser, Item> Iterator for Many1Parser<'a, Parser, Item>
-where
-    Parser: SynParserIter<'a, Item>,
-{
-    type Item = SynParserResult<'a, Vec<Item>>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let x = Parser::with_tokens(self.tokens).next()?;
-        let mut v = vec![x.item];
-        let mut count = x.tokens.len();
-
-        while let Some(y) = Parser::with_tokens(&self.tokens[count..]).next() {
-            v.push(y.item);
-            count += y.tokens.len();
-        }
-
-        Some(SynParserResult {
-            item: v,
-            tokens: &self.tokens[..count],
-        })
-    }
-}
-
 /// brackets parser.
 #[derive(Debug, Clone)]
 struct BracParser<'a, NodeParser>
@@ -187,6 +196,7 @@ where
     NodeParser: SynParserIter<'a, SynNode>,
 {
     tokens: &'a [Token],
+    depth: usize,
     node_parser: PhantomData<NodeParser>,
 }
 
@@ -195,8 +205,13 @@ where
     NodeParser: SynParserIter<'a, SynNode>,
 {
     fn with_tokens(tokens: &'a [Token]) -> Self {
+        Self::with_depth(tokens, 0)
+    }
+
+    fn with_depth(tokens: &'a [Token], depth: usize) -> Self {
         Self {
             tokens,
+            depth,
             node_parser: PhantomData,
         }
     }
@@ -212,15 +227,61 @@ where
         // Open bracket
         let open_brac = OpenBracTokenParser::with_tokens(self.tokens).next()?;
 
+        // Too deep, the brackets are skipped without parsing inside them
+        if self.depth >= SynParser::MAX_DEPTH {
+            let TokenKind::OpenBrac(open) = open_brac.item.value else {
+                unreachable!()
+            };
+            let end = too_deep_len(self.tokens.iter().map(|x| &x.value));
+            let span = open_brac.item.span.to(&self.tokens[end - 1].span);
+
+            return Some(SynParserResult {
+                item: SynNode {
+                    value: SynNodeKind::Error {
+                        kind: SynErrorKind::TooDeep {
+                            open: Spanned::new(open, open_brac.item.span),
+                        },
+                        children: vec![],
+                    },
+                    span,
+                },
+                tokens: &self.tokens[..end],
+            });
+        }
+
         // Items
         let items_start = open_brac.tokens.len();
-        let items = ManyParser::<'a, NodeParser, SynNode>::with_tokens(&self.tokens[items_start..])
-            .next()?;
-        let items_span_len = items.item.iter().fold(0, |acc, x| acc + x.span.len);
+        let items = ManyParser::<'a, NodeParser, SynNode>::with_depth(
+            &self.tokens[items_start..],
+            self.depth + 1,
+        )
+        .next()?;
 
-        // Close bracket
+        // Close bracket, the items run to the end of the tokens if it is missing
         let items_end = items_start + items.tokens.len();
-        let close_brac = CloseBracTokenParser::with_tokens(&self.tokens[items_end..]).next()?;
+        let Some(close_brac) = CloseBracTokenParser::with_tokens(&self.tokens[items_end..]).next()
+        else {
+            let TokenKind::OpenBrac(open) = open_brac.item.value else {
+                unreachable!()
+            };
+            let span = match items.item.last() {
+                Some(last) => open_brac.item.span.to(&last.span),
+                None => open_brac.item.span.clone(),
+            };
+
+            return Some(SynParserResult {
+                item: SynNode {
+                    value: SynNodeKind::Error {
+                        kind: SynErrorKind::UnclosedBrac {
+                            open: Spanned::new(open, open_brac.item.span),
+                        },
+                        children: items.item,
+                    },
+                    span,
+                },
+                tokens: &self.tokens[..items_end],
+            });
+        };
 
         fn match_brac(open: char, close: char) -> bool {
             match (open, close) {
@@ -229,6 +290,7 @@ where
             }
         }
 
+        let span = open_brac.item.span.to(&close_brac.item.span);
         let value = match (open_brac.item.value, close_brac.item.value) {
             (TokenKind::OpenBrac(open), TokenKind::CloseBrac(close)) if match_brac(open, close) => {
                 SynNodeKind::Brac {
@@ -238,7 +300,10 @@ where
                 }
             }
             (TokenKind::OpenBrac(open), TokenKind::CloseBrac(close)) => SynNodeKind::Error {
-                msg: format!("Mismatched brackets: '{}' and '{}'", open, close),
+                kind: SynErrorKind::MismatchedBrac {
+                    open: Spanned::new(open, open_brac.item.span),
+                    close: Spanned::new(close, close_brac.item.span),
+                },
                 children: items.item,
             },
             _ => unreachable!(),
@@ -246,13 +311,7 @@ where
         let end = items_end + close_brac.tokens.len();
 
         Some(SynParserResult {
-            item: SynNode {
-                value,
-                span: Span {
-                    start: open_brac.item.span.start,
-                    len: open_brac.item.span.len + items_span_len + close_brac.item.span.len,
-                },
-            },
+            item: SynNode { value, span },
             tokens: &self.tokens[..end],
         })
     }
@@ -292,15 +351,91 @@ impl<'a> Iterator for IdentParser<'a> {
     }
 }
 
+/// String parser.
+#[derive(Debug, Clone)]
+struct StrParser<'a> {
+    tokens: &'a [Token],
+}
+
+impl<'a> SynParserIter<'a, SynNode> for StrParser<'a> {
+    fn with_tokens(tokens: &'a [Token]) -> Self {
+        Self { tokens }
+    }
+}
+
+impl<'a> Iterator for StrParser<'a> {
+    type Item = SynParserResult<'a, SynNode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = StrTokenParser::with_tokens(self.tokens).next()?;
+        let Token {
+            value: TokenKind::Str(value),
+            ..
+        } = token.item
+        else {
+            unreachable!()
+        };
+        Some(SynParserResult {
+            item: SynNode {
+                value: SynNodeKind::Str(value),
+                span: token.item.span,
+            },
+            tokens: token.tokens,
+        })
+    }
+}
+
+/// Token error parser.
+#[derive(Debug, Clone)]
+struct TokenErrorParser<'a> {
+    tokens: &'a [Token],
+}
+
+impl<'a> SynParserIter<'a, SynNode> for TokenErrorParser<'a> {
+    fn with_tokens(tokens: &'a [Token]) -> Self {
+        Self { tokens }
+    }
+}
+
+impl<'a> Iterator for TokenErrorParser<'a> {
+    type Item = SynParserResult<'a, SynNode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = ErrorTokenParser::with_tokens(self.tokens).next()?;
+        let Token {
+            value: TokenKind::Error(kind),
+            span,
+        } = token.item
+        else {
+            unreachable!()
+        };
+        Some(SynParserResult {
+            item: SynNode {
+                value: SynNodeKind::Error {
+                    kind: SynErrorKind::Token(kind),
+                    children: vec![],
+                },
+                span,
+            },
+            tokens: token.tokens,
+        })
+    }
+}
+
 /// Syntactic node parser.
 #[derive(Debug, Clone)]
 struct SynNodeParser<'a> {
     tokens: &'a [Token],
+    depth: usize,
 }
 
 impl<'a> SynParserIter<'a, SynNode> for SynNodeParser<'a> {
     fn with_tokens(tokens: &'a [Token]) -> Self {
-        Self { tokens }
+        Self::with_depth(tokens, 0)
+    }
+
+    fn with_depth(tokens: &'a [Token], depth: usize) -> Self {
+        Self { tokens, depth }
     }
 }
 
@@ -310,7 +445,13 @@ impl<'a> Iterator for SynNodeParser<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(x) = IdentParser::with_tokens(self.tokens).next() {
             Some(x)
-        } else if let Some(x) = BracParser::<'a, SynNodeParser>::with_tokens(self.tokens).next() {
+        } else if let Some(x) = StrParser::with_tokens(self.tokens).next() {
+            Some(x)
+        } else if let Some(x) = TokenErrorParser::with_tokens(self.tokens).next() {
+            Some(x)
+        } else if let Some(x) =
+            BracParser::<'a, SynNodeParser>::with_depth(self.tokens, self.depth).next()
+        {
             Some(x)
         } else {
             None
@@ -318,6 +459,70 @@ impl<'a> Iterator for SynNodeParser<'a> {
     }
 }
 
+/// Unexpected closing bracket parser.
+#[derive(Debug, Clone)]
+struct UnexpectedCloseBracParser<'a> {
+    tokens: &'a [Token],
+}
+
+impl<'a> SynParserIter<'a, SynNode> for UnexpectedCloseBracParser<'a> {
+    fn with_tokens(tokens: &'a [Token]) -> Self {
+        Self { tokens }
+    }
+}
+
+impl<'a> Iterator for UnexpectedCloseBracParser<'a> {
+    type Item = SynParserResult<'a, SynNode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = CloseBracTokenParser::with_tokens(self.tokens).next()?;
+        let Token {
+            value: TokenKind::CloseBrac(close),
+            span,
+        } = token.item
+        else {
+            unreachable!()
+        };
+        Some(SynParserResult {
+            item: SynNode {
+                value: SynNodeKind::Error {
+                    kind: SynErrorKind::UnexpectedCloseBrac {
+                        close: Spanned::new(close, span.clone()),
+                    },
+                    children: vec![],
+                },
+                span,
+            },
+            tokens: token.tokens,
+        })
+    }
+}
+
+/// Program node parser.
+///
+/// Parses a syntactic node, or a closing bracket that has no opening one,
+/// so that parsing can continue after it.
+#[derive(Debug, Clone)]
+struct ProgramNodeParser<'a> {
+    tokens: &'a [Token],
+}
+
+impl<'a> SynParserIter<'a, SynNode> for ProgramNodeParser<'a> {
+    fn with_tokens(tokens: &'a [Token]) -> Self {
+        Self { tokens }
+    }
+}
+
+impl<'a> Iterator for ProgramNodeParser<'a> {
+    type Item = SynParserResult<'a, SynNode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        SynNodeParser::with_tokens(self.tokens)
+            .next()
+            .or_else(|| UnexpectedCloseBracParser::with_tokens(self.tokens).next())
+    }
+}
+
 /// Program parser.
 #[derive(Debug, Clone)]
 struct ProgramParser<'a> {
@@ -334,6 +539,160 @@ impl<'a> Iterator for ProgramParser<'a> {
     type Item = SynParserResult<'a, Vec<SynNode>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        ManyParser::<'a, SynNodeParser, SynNode>::with_tokens(self.tokens).next()
+        ManyParser::<'a, ProgramNodeParser, SynNode>::with_tokens(self.tokens).next()
+    }
+}
+
+/// Get the number of tokens of brackets nested too deep to be parsed, up to
+/// their closing bracket, or up to the last token that is not trivia if
+/// they are never closed.
+pub(crate) fn too_deep_len<'a>(tokens: impl Iterator<Item = &'a TokenKind>) -> usize {
+    let mut depth = 0;
+    let mut len = 0;
+    for (i, kind) in tokens.enumerate() {
+        match kind {
+            TokenKind::OpenBrac(_) => depth += 1,
+            TokenKind::CloseBrac(_) if depth == 1 => return i + 1,
+            TokenKind::CloseBrac(_) => depth -= 1,
+            kind if kind.is_trivia() => continue,
+            _ => {}
+        }
+        len = i + 1;
+    }
+    len
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Interner, SrcCodeIterExt};
+
+    fn parse(src: &str, interner: &mut Interner) -> Vec<SynNode> {
+        src.char_indices()
+            .src_code()
+            .lexer(interner)
+            .parse_syn()
+            .parse()
+    }
+
+    fn error_msgs(nodes: &[SynNode]) -> Vec<String> {
+        nodes
+            .iter()
+            .flat_map(|node| match &node.value {
+                SynNodeKind::Ident(_) | SynNodeKind::Str(_) => vec![],
+                SynNodeKind::Brac { children, .. } => error_msgs(children),
+                SynNodeKind::Error { kind, children } => std::iter::once(kind.to_string())
+                    .chain(error_msgs(children))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_syn_parser_unclosed_brac() {
+        let nodes = parse("a {\n    b ( c", &mut Interner::new());
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(
+            error_msgs(&nodes),
+            [
+                "Unclosed bracket '{' opened at 1:3",
+                "Unclosed bracket '(' opened at 2:7",
+            ],
+        );
+        assert_eq!(nodes[1].span.end_idx(), "a {\n    b ( c".len());
+    }
+
+    #[test]
+    fn test_syn_parser_too_deep() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        let mut interner = Interner::new();
+
+        let nodes = parse(&nested(SynParser::MAX_DEPTH), &mut interner);
+        assert!(error_msgs(&nodes).is_empty());
+
+        // Parsing goes on after the brackets nested too deep
+        let src = format!("{} b", nested(100_000));
+        let nodes = parse(&src, &mut interner);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(
+            error_msgs(&nodes),
+            ["Brackets nested too deep: more than 256 levels"],
+        );
+        assert_eq!(nodes[0].span.end_idx(), src.len() - " b".len());
+
+        let src = format!("{} b ) c", "(".repeat(100_000));
+        let nodes = parse(&src, &mut interner);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].span.end_idx(), src.len());
+    }
+
+    #[test]
+    fn test_syn_parser_unexpected_close_brac() {
+        let nodes = parse("a ) b { c } }", &mut Interner::new());
+        assert_eq!(nodes.len(), 5);
+        assert_eq!(
+            error_msgs(&nodes),
+            [
+                "Unexpected closing bracket ')'",
+                "Unexpected closing bracket '}'",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_syn_parser_resync_after_mismatched_brac() {
+        let mut interner = Interner::new();
+        let nodes = parse("( a } b { c )", &mut interner);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[1].value, SynNodeKind::Ident(interner.intern("b")));
+        assert_eq!(
+            error_msgs(&nodes),
+            [
+                "Mismatched brackets: '(' and '}'",
+                "Mismatched brackets: '{' and ')'",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_syn_parser_str() {
+        let mut interner = Interner::new();
+        let nodes = parse(r#"say "a (b) {c}" "\"\\\n\t\u{1F600}"x"#, &mut interner);
+        assert_eq!(
+            nodes.iter().map(|x| x.value.clone()).collect::<Vec<_>>(),
+            [
+                SynNodeKind::Ident(interner.intern("say")),
+                SynNodeKind::Str("a (b) {c}".into()),
+                SynNodeKind::Str("\"\\\n\t😀".into()),
+                SynNodeKind::Ident(interner.intern("x")),
+            ],
+        );
+        assert_eq!(nodes[2].span.len, r#""\"\\\n\t\u{1F600}""#.len());
+
+        let nodes = parse("a \"b\\q \\u{110000}\" c\nd \"e (\nf", &mut interner);
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(
+            error_msgs(&nodes),
+            ["Invalid escape '\\q' in string", "Unterminated string"],
+        );
+        assert_eq!(nodes[4].span.len, "\"e (".len());
+        assert_eq!(nodes[5].value, SynNodeKind::Ident(interner.intern("f")));
+    }
+
+    #[test]
+    fn test_syn_parser_is_incomplete() {
+        let is_incomplete = |src: &str| {
+            src.char_indices()
+                .src_code()
+                .lexer(&mut Interner::new())
+                .parse_syn()
+                .is_incomplete()
+        };
+        assert!(is_incomplete("a {\n    b ( c"));
+        assert!(is_incomplete("a {}\nb { \"c\""));
+        assert!(!is_incomplete("a { b }"));
+        assert!(!is_incomplete("a { ( }"));
+        assert!(!is_incomplete("a { b ) c"));
+        assert!(!is_incomplete("a { \"b\\q\""));
     }
 }
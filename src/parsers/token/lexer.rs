@@ -1,38 +1,41 @@
 use super::*;
 use crate::parsers::SrcCode;
-use crate::utils::NextRangePeek;
-use crate::{Span, SynParser};
+use crate::utils::{AdvanceIterExt, Interner, NextRangePeek};
+use crate::{Span, Spanned, SynParser};
 
 /// Lexer.
-#[derive(Debug, Clone)]
-pub struct Lexer<Iter>
+///
+/// Identifiers are interned into `interner`.
+#[derive(Debug)]
+pub struct Lexer<'i, Iter>
 where
     Iter: Iterator<Item = SrcCode>,
 {
     iter: NextRangePeek<Iter>,
+    interner: &'i mut Interner,
 }
 
-impl<Iter> Lexer<Iter>
+impl<'i, Iter> Lexer<'i, Iter>
 where
     Iter: Iterator<Item = SrcCode>,
 {
     /// Create a new lexer.
-    pub fn new(iter: NextRangePeek<Iter>) -> Self {
-        Self { iter }
+    pub fn new(iter: NextRangePeek<Iter>, interner: &'i mut Interner) -> Self {
+        Self { iter, interner }
     }
 
-    /// Ignore spaces and newlines.
-    pub fn ignore_spaces_and_newlines(self) -> impl Iterator<Item = Token> {
-        self.filter(|t| !t.value.is_spaces() && !t.value.is_newlines())
+    /// Ignore trivia: spaces, newlines and comments.
+    pub fn ignore_trivia(self) -> impl Iterator<Item = Token> + use<'i, Iter> {
+        self.filter(|t| !t.value.is_trivia())
     }
 
     /// Parse syntax.
     pub fn parse_syn(self) -> SynParser {
-        SynParser::new(self.ignore_spaces_and_newlines().collect())
+        SynParser::new(self.ignore_trivia().collect())
     }
 }
 
-impl<Iter> Iterator for Lexer<Iter>
+impl<Iter> Iterator for Lexer<'_, Iter>
 where
     Iter: Iterator<Item = SrcCode>,
 {
@@ -43,7 +46,9 @@ where
             .or_else(|| parse_close_brac(&mut self.iter))
             .or_else(|| parse_newlines(&mut self.iter))
             .or_else(|| parse_spaces(&mut self.iter))
-            .or_else(|| parse_ident(&mut self.iter))
+            .or_else(|| parse_comment(&mut self.iter))
+            .or_else(|| parse_str(&mut self.iter))
+            .or_else(|| parse_ident(&mut self.iter, self.interner))
     }
 }
 
@@ -81,16 +86,15 @@ where
     match iter.peek_while(|c| ['\n', '\r'].contains(&c.value)) {
         [] => None,
         codes => {
-            let len = codes.len();
+            let count = codes.len();
+            let len = codes.iter().map(|c| c.span.len).sum();
             let first = iter.next().unwrap();
-            for _ in 1..len {
-                iter.next();
-            }
+            iter.advance(count - 1);
 
             Some(
                 first
                     .map(|_| TokenKind::Newlines)
-                    .map_span(|span| Span::new(span.start, len)),
+                    .map_span(|span| Span { len, ..span }),
             )
         }
     }
@@ -101,49 +105,246 @@ fn parse_spaces<Iter>(iter: &mut NextRangePeek<Iter>) -> Option<Token>
 where
     Iter: Iterator<Item = SrcCode>,
 {
-    match iter.peek_while(|c| c.value.is_whitespace()) {
+    match iter.peek_while(|c| c.value.is_whitespace() && !['\n', '\r'].contains(&c.value)) {
         [] => None,
         codes => {
-            let len = codes.len();
+            let count = codes.len();
+            let len = codes.iter().map(|c| c.span.len).sum();
             let first = iter.next().unwrap();
-            for _ in 1..len {
-                iter.next();
-            }
+            iter.advance(count - 1);
 
             Some(
                 first
                     .map(|_| TokenKind::Spaces)
-                    .map_span(|span| Span::new(span.start, len)),
+                    .map_span(|span| Span { len, ..span }),
             )
         }
     }
 }
 
+/// Parse source code into [`TokenKind::Comment`].
+///
+/// Comments start with `//` at the start of a token and end at their line,
+/// so `a//b` is still an identifier.
+fn parse_comment<Iter>(iter: &mut NextRangePeek<Iter>) -> Option<Token>
+where
+    Iter: Iterator<Item = SrcCode>,
+{
+    match iter.peek(2) {
+        [a, b] if a.value == '/' && b.value == '/' => {}
+        _ => return None,
+    }
+
+    // The scan resumes after the slashes, they are already known to not be newlines
+    let codes = iter.peek_while_from(2, |c| !['\n', '\r'].contains(&c.value));
+    let count = codes.len();
+    let len = codes.iter().map(|c| c.span.len).sum();
+    let text = codes[2..].iter().map(|c| c.value).collect::<String>();
+    let first = iter.next().unwrap();
+    iter.advance(count - 1);
+
+    Some(
+        first
+            .map(|_| TokenKind::Comment(text))
+            .map_span(|span| Span { len, ..span }),
+    )
+}
+
+/// Parse source code into [`TokenKind::Str`].
+///
+/// Strings end at their line, a string without a closing quote and a string
+/// with an invalid escape are parsed into [`TokenKind::Error`].
+fn parse_str<Iter>(iter: &mut NextRangePeek<Iter>) -> Option<Token>
+where
+    Iter: Iterator<Item = SrcCode>,
+{
+    match iter.peek(1) {
+        [c] if c.value == '"' => {}
+        _ => return None,
+    }
+
+    let open = iter.next().unwrap();
+    let mut len = open.span.len;
+    let mut value = String::new();
+    let mut invalid_escape = None;
+    let terminated = loop {
+        let code = match iter.peek(1) {
+            [c] if !['\n', '\r'].contains(&c.value) => iter.next().unwrap(),
+            _ => break false,
+        };
+        len += code.span.len;
+        match code.value {
+            '"' => break true,
+            '\\' => match parse_escape(iter, code) {
+                (Ok(c), escape_len) => {
+                    len += escape_len;
+                    value.push(c);
+                }
+                (Err(escape), escape_len) => {
+                    len += escape_len;
+                    invalid_escape.get_or_insert(escape);
+                }
+            },
+            c => value.push(c),
+        }
+    };
+
+    let kind = match (terminated, invalid_escape) {
+        (false, _) => TokenKind::Error(TokenErrorKind::UnterminatedStr {
+            open: Spanned::new(open.value, open.span.clone()),
+        }),
+        (true, Some(escape)) => TokenKind::Error(TokenErrorKind::InvalidEscape { escape }),
+        (true, None) => TokenKind::Str(value.into()),
+    };
+    Some(Token::new(kind, Span { len, ..open.span }))
+}
+
+/// Parse an escape sequence after its backslash.
+///
+/// Returns the escaped character, or the escape sequence if it is invalid,
+/// and the length of the codes consumed after the backslash.
+fn parse_escape<Iter>(
+    iter: &mut NextRangePeek<Iter>,
+    backslash: SrcCode,
+) -> (Result<char, Spanned<String>>, usize)
+where
+    Iter: Iterator<Item = SrcCode>,
+{
+    let mut codes = vec![backslash];
+    if let [c] = iter.peek(1) {
+        if !['\n', '\r'].contains(&c.value) {
+            codes.push(iter.next().unwrap());
+        }
+    }
+
+    let escaped = match codes.get(1).map(|c| c.value) {
+        Some('"') => Some('"'),
+        Some('\\') => Some('\\'),
+        Some('n') => Some('\n'),
+        Some('r') => Some('\r'),
+        Some('t') => Some('\t'),
+        Some('0') => Some('\0'),
+        // `\u{..}` with 1 to 6 hexadecimal digits
+        Some('u') => {
+            while let [c] = iter.peek(1) {
+                let c = c.value;
+                let next = match codes.len() {
+                    2 => c == '{',
+                    _ => c.is_ascii_hexdigit() || c == '}',
+                };
+                if !next {
+                    break;
+                }
+                codes.push(iter.next().unwrap());
+                if c == '}' {
+                    break;
+                }
+            }
+            match codes.last().map(|c| c.value) {
+                Some('}') if (5..=10).contains(&codes.len()) => {
+                    let digits = codes[3..codes.len() - 1]
+                        .iter()
+                        .map(|c| c.value)
+                        .collect::<String>();
+                    u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let len = codes[1..].iter().map(|c| c.span.len).sum();
+    let result = escaped.ok_or_else(|| {
+        let span = codes[0].span.to(&codes[codes.len() - 1].span);
+        Spanned::new(codes.iter().map(|c| c.value).collect(), span)
+    });
+    (result, len)
+}
+
 /// Parse source code into [`TokenKind::Ident`].
-fn parse_ident<Iter>(iter: &mut NextRangePeek<Iter>) -> Option<Token>
+fn parse_ident<Iter>(iter: &mut NextRangePeek<Iter>, interner: &mut Interner) -> Option<Token>
 where
     Iter: Iterator<Item = SrcCode>,
 {
     match iter.peek_while(|c| {
-        !c.value.is_whitespace() && !['(', ')', '{', '}', '\n', '\r'].contains(&c.value)
+        !c.value.is_whitespace() && !['(', ')', '{', '}', '"', '\n', '\r'].contains(&c.value)
     }) {
         [] => None,
         codes => {
-            let len = codes.len();
+            let count = codes.len();
+            let len = codes.iter().map(|c| c.span.len).sum();
             let mut ident = String::with_capacity(len);
 
             let first = iter.next().unwrap();
             ident.push(first.value);
-            for _ in 1..len {
+            for _ in 1..count {
                 let code = iter.next().unwrap();
                 ident.push(code.value);
             }
 
             Some(
                 first
-                    .map(|_| TokenKind::Ident(ident))
-                    .map_span(|span| Span::new(span.start, len)),
+                    .map(|_| TokenKind::Ident(interner.intern(&ident)))
+                    .map_span(|span| Span { len, ..span }),
             )
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SrcCodeIterExt;
+
+    fn lex(src: &str, interner: &mut Interner) -> Vec<Token> {
+        src.char_indices().src_code().lexer(interner).collect()
+    }
+
+    #[test]
+    fn test_lexer_comment() {
+        let mut interner = Interner::new();
+        let src = "a//b // c ) \"d\n//\ne";
+        let tokens = lex(src, &mut interner)
+            .into_iter()
+            .filter(|t| !t.value.is_spaces() && !t.value.is_newlines())
+            .map(|t| (t.value, &src[t.span.start.idx..t.span.end_idx()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::Ident(interner.intern("a//b")), "a//b"),
+                (TokenKind::Comment(" c ) \"d".into()), "// c ) \"d"),
+                (TokenKind::Comment("".into()), "//"),
+                (TokenKind::Ident(interner.intern("e")), "e"),
+            ],
+        );
+
+        // Comments are not identifiers, so they are not interned
+        assert_eq!(interner.len(), 2);
+
+        let nodes = src
+            .char_indices()
+            .src_code()
+            .lexer(&mut interner)
+            .parse_syn()
+            .parse();
+        assert_eq!(nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_lexer_str() {
+        let mut interner = Interner::new();
+        let src = "\"lexed \\\"string\\\" data\"";
+        let tokens = lex(src, &mut interner)
+            .into_iter()
+            .map(|t| t.value)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, [TokenKind::Str("lexed \"string\" data".into())]);
+
+        // Strings are data, not identifiers, so they are not interned
+        assert!(interner.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 mod lexer;
 pub use lexer::*;
+#[allow(clippy::module_inception)]
 mod token;
 pub use token::*;
@@ -1,4 +1,6 @@
-use crate::utils::SimpleDisplay;
+use std::sync::Arc;
+
+use crate::utils::{Interner, SimpleDisplay, Symbol};
 use crate::Spanned;
 use strum_macros::EnumIs;
 
@@ -12,20 +14,77 @@ pub enum TokenKind {
     CloseBrac(char),
 
     /** Identifier */
-    Ident(String),
+    Ident(Symbol),
+
+    /** String: a quoted string, with its escapes decoded */
+    Str(Arc<str>),
 
     /** Spaces */
     Spaces,
 
     /** Newline */
     Newlines,
+
+    /** Comment: a line comment, the text after its `//` */
+    Comment(String),
+
+    /** Error: characters that do not form a token */
+    Error(TokenErrorKind),
+}
+
+impl TokenKind {
+    /// Check if the token is trivia: spaces, newlines and comments,
+    /// which carry no meaning for the parsers.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Spaces | TokenKind::Newlines | TokenKind::Comment(_)
+        )
+    }
+}
+
+/// Token error kind.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, thiserror::Error)]
+pub enum TokenErrorKind {
+    /// Unterminated string: a string without a closing quote on its line
+    #[error("Unterminated string")]
+    UnterminatedStr { open: Spanned<char> },
+
+    /// Invalid escape: an escape sequence that is not supported in strings
+    #[error("Invalid escape '{}' in string", .escape.value)]
+    InvalidEscape { escape: Spanned<String> },
 }
 
 /// Token.
 pub type Token = Spanned<TokenKind>;
 
 impl SimpleDisplay for Token {
-    fn simple_display(&self) -> String {
-        format!("{:?}", self.value)
+    fn simple_display(&self, interner: &Interner) -> String {
+        match &self.value {
+            TokenKind::Ident(ident) => format!("Ident({:?})", interner.resolve(*ident)),
+            kind => format!("{kind:?}"),
+        }
+    }
+}
+
+/// Quote a string the way it is written in source code.
+///
+/// Quotes, backslashes, newlines and tabs are escaped, other control
+/// characters use `\u{..}`.
+pub fn quote_str(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
     }
+    quoted.push('"');
+    quoted
 }
@@ -4,3 +4,5 @@ mod simple_display;
 pub use simple_display::*;
 mod next_range_peek;
 pub use next_range_peek::*;
+mod symbol;
+pub use symbol::*;
@@ -1,11 +1,16 @@
+use std::collections::VecDeque;
+
 /// Peekable iterator that can peek at the next range of items.
+///
+/// Peeked items are kept in a ring buffer, so `next` is amortised O(1). The
+/// buffer is only rearranged when a peeked range wraps around its end.
 #[derive(Debug)]
 pub struct NextRangePeek<Iter>
 where
     Iter: Iterator,
 {
     iter: Iter,
-    peeked: Vec<Iter::Item>,
+    peeked: VecDeque<Iter::Item>,
 }
 
 impl<I> NextRangePeek<I>
@@ -16,7 +21,7 @@ where
     pub fn new(iter: I) -> Self {
         Self {
             iter,
-            peeked: Vec::new(),
+            peeked: VecDeque::new(),
         }
     }
 
@@ -24,43 +29,62 @@ where
     pub fn peek(&mut self, n: usize) -> &[I::Item] {
         while self.peeked.len() < n {
             if let Some(item) = self.iter.next() {
-                self.peeked.push(item);
+                self.peeked.push_back(item);
             } else {
                 break;
             }
         }
 
-        &self.peeked[..std::cmp::min(n, self.peeked.len())]
+        let n = std::cmp::min(n, self.peeked.len());
+        self.front(n)
     }
 
     /// Peek while the condition is true.
-    pub fn peek_while<F>(&mut self, mut f: F) -> &[I::Item]
+    pub fn peek_while<F>(&mut self, f: F) -> &[I::Item]
     where
         F: FnMut(&I::Item) -> bool,
     {
-        let mut matched_count = self.peeked.iter().take_while(|x| f(x)).count();
-        if matched_count < self.peeked.len() {
-            return &self.peeked[..matched_count];
-        }
+        self.peek_while_from(0, f)
+    }
 
-        while let Some(item) = self.iter.next() {
-            if f(&item) {
-                self.peeked.push(item);
-                matched_count += 1;
-            } else {
-                self.peeked.push(item);
-                break;
+    /// Peek while the condition is true, from the `start`-th item.
+    ///
+    /// The first `start` items are taken as matching without calling `f`,
+    /// so a scan can resume from the length of a previous `peek_while` with
+    /// the same condition. `start` is capped to the peeked items.
+    pub fn peek_while_from<F>(&mut self, start: usize, mut f: F) -> &[I::Item]
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        let mut matched = std::cmp::min(start, self.peeked.len());
+        matched += self.peeked.range(matched..).take_while(|x| f(x)).count();
+        if matched == self.peeked.len() {
+            while let Some(item) = self.iter.next() {
+                let is_match = f(&item);
+                self.peeked.push_back(item);
+                if !is_match {
+                    break;
+                }
+                matched += 1;
             }
         }
 
-        &self.peeked[..std::cmp::min(matched_count, self.peeked.len())]
+        self.front(matched)
+    }
+
+    /// Get the first `n` peeked items as a slice.
+    fn front(&mut self, n: usize) -> &[I::Item] {
+        if self.peeked.as_slices().0.len() < n {
+            self.peeked.make_contiguous();
+        }
+        &self.peeked.as_slices().0[..n]
     }
 }
 
 impl<Iter> Clone for NextRangePeek<Iter>
 where
     Iter: Iterator + Clone,
-    Vec<<Iter as Iterator>::Item>: Clone,
+    VecDeque<<Iter as Iterator>::Item>: Clone,
 {
     fn clone(&self) -> Self {
         Self {
@@ -77,10 +101,9 @@ where
     type Item = Iter::Item;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.peeked.is_empty() {
-            self.iter.next()
-        } else {
-            Some(self.peeked.remove(0))
+        match self.peeked.pop_front() {
+            Some(item) => Some(item),
+            None => self.iter.next(),
         }
     }
 }
@@ -105,6 +128,121 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
+
+    /// Previous implementation, shifting a vector on every `next`.
+    struct Reference<I: Iterator> {
+        iter: I,
+        peeked: Vec<I::Item>,
+    }
+
+    impl<I: Iterator> Reference<I> {
+        fn peek(&mut self, n: usize) -> &[I::Item] {
+            while self.peeked.len() < n {
+                match self.iter.next() {
+                    Some(item) => self.peeked.push(item),
+                    None => break,
+                }
+            }
+            &self.peeked[..std::cmp::min(n, self.peeked.len())]
+        }
+
+        fn peek_while(&mut self, mut f: impl FnMut(&I::Item) -> bool) -> &[I::Item] {
+            let mut matched_count = self.peeked.iter().take_while(|x| f(x)).count();
+            if matched_count < self.peeked.len() {
+                return &self.peeked[..matched_count];
+            }
+            for item in self.iter.by_ref() {
+                let is_match = f(&item);
+                self.peeked.push(item);
+                if !is_match {
+                    break;
+                }
+                matched_count += 1;
+            }
+            &self.peeked[..matched_count]
+        }
+
+        fn next(&mut self) -> Option<I::Item> {
+            match self.peeked.is_empty() {
+                true => self.iter.next(),
+                false => Some(self.peeked.remove(0)),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Next,
+        Peek(usize),
+        PeekWhileEven,
+        PeekWhileSmall,
+        PeekWhileBelow(u8),
+        PeekWhileSmallFrom(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            Just(Op::Next),
+            (0..8usize).prop_map(Op::Peek),
+            Just(Op::PeekWhileEven),
+            Just(Op::PeekWhileSmall),
+            any::<u8>().prop_map(Op::PeekWhileBelow),
+            (0..8usize).prop_map(Op::PeekWhileSmallFrom),
+        ]
+    }
+
+    fn apply<I>(peek: &mut NextRangePeek<I>, op: &Op) -> Vec<u8>
+    where
+        I: Iterator<Item = u8>,
+    {
+        match op {
+            Op::Next => peek.next().into_iter().collect(),
+            Op::Peek(n) => peek.peek(*n).to_vec(),
+            Op::PeekWhileEven => peek.peek_while(|x| x % 2 == 0).to_vec(),
+            Op::PeekWhileSmall => peek.peek_while(|x| *x < 64).to_vec(),
+            Op::PeekWhileBelow(max) => {
+                let max = *max;
+                peek.peek_while(move |x| *x < max).to_vec()
+            }
+            Op::PeekWhileSmallFrom(start) => {
+                // Resume from at most the items known to match
+                let start = std::cmp::min(*start, peek.peek_while(|x| *x < 64).len());
+                peek.peek_while_from(start, |x| *x < 64).to_vec()
+            }
+        }
+    }
+
+    fn apply_reference<I>(peek: &mut Reference<I>, op: &Op) -> Vec<u8>
+    where
+        I: Iterator<Item = u8>,
+    {
+        match op {
+            Op::Next => peek.next().into_iter().collect(),
+            Op::Peek(n) => peek.peek(*n).to_vec(),
+            Op::PeekWhileEven => peek.peek_while(|x| x % 2 == 0).to_vec(),
+            Op::PeekWhileSmall | Op::PeekWhileSmallFrom(_) => peek.peek_while(|x| *x < 64).to_vec(),
+            Op::PeekWhileBelow(max) => peek.peek_while(|x| x < max).to_vec(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_next_range_peek_matches_reference(
+            input in prop::collection::vec(any::<u8>(), 0..64),
+            ops in prop::collection::vec(op(), 0..128),
+        ) {
+            let mut peek = input.clone().into_iter().next_range_peek();
+            let mut reference = Reference { iter: input.into_iter(), peeked: vec![] };
+
+            for op in &ops {
+                let expected = apply_reference(&mut reference, op);
+                prop_assert_eq!(apply(&mut peek, op), expected, "{:?}", op);
+            }
+            let rest = std::iter::from_fn(|| reference.next()).collect::<Vec<_>>();
+            prop_assert_eq!(peek.collect::<Vec<_>>(), rest);
+        }
+    }
 
     #[test]
     fn test_next_range_peek_peek() {
@@ -136,4 +274,15 @@ mod test {
         assert_eq!(peek.next(), Some(&5));
         assert_eq!(peek.next(), None);
     }
+
+    #[test]
+    fn test_next_range_peek_peek_while_from() {
+        let input = vec![1, 2, 3, 4, 5];
+        let mut peek = input.iter().next_range_peek();
+        assert_eq!(peek.peek_while(|x| *x < &3), &[&1, &2]);
+        assert_eq!(peek.peek_while_from(2, |x| *x < &5), &[&1, &2, &3, &4]);
+        assert_eq!(peek.peek_while_from(9, |x| *x < &6), &[&1, &2, &3, &4, &5]);
+        assert_eq!(peek.next(), Some(&1));
+        assert_eq!(peek.peek_while_from(1, |x| **x != 4), &[&2, &3]);
+    }
 }
@@ -1,5 +1,7 @@
+use crate::Interner;
+
 /// Trait for creating a simple display string.
 pub trait SimpleDisplay {
-    /// Create a simple display string.
-    fn simple_display(&self) -> String;
+    /// Create a simple display string, with symbols resolved through `interner`.
+    fn simple_display(&self, interner: &Interner) -> String;
 }
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Interned identifier.
+///
+/// Symbols are small ids into an [`Interner`], so they are `Copy` and
+/// compare in O(1). They resolve back to their text through the interner
+/// that created them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/// Symbol interner.
+///
+/// Owned by the interpreter and threaded from the lexer to the definition
+/// stack, so its texts are dropped with it.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    /// Interned texts, indexed by symbol.
+    texts: Vec<Arc<str>>,
+    symbols: HashMap<Arc<str>, Symbol>,
+}
+
+impl Interner {
+    /// Create a new empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Intern text, returning the existing symbol if any.
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(text) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.texts.len() as u32);
+        let text: Arc<str> = text.into();
+        self.symbols.insert(text.clone(), symbol);
+        self.texts.push(text);
+        symbol
+    }
+
+    /// Get the symbol of text if it is already interned.
+    pub fn get(&self, text: &str) -> Option<Symbol> {
+        self.symbols.get(text).copied()
+    }
+
+    /// Get the text of a symbol.
+    ///
+    /// Panics if the symbol comes from another interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.texts[symbol.0 as usize]
+    }
+
+    /// Get the number of interned texts.
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// Check if no text is interned.
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_symbol_intern() {
+        let mut interner = Interner::new();
+        let a = interner.intern("a");
+        assert_eq!(a, interner.intern("a"));
+        assert_ne!(a, interner.intern("b"));
+        assert_eq!(interner.resolve(a), "a");
+        assert_eq!(interner.get("a"), Some(a));
+        assert_eq!(interner.get("c"), None);
+        assert_eq!(interner.len(), 2);
+
+        // Interners are independent, and their texts are dropped with them
+        let other = Interner::new();
+        assert_eq!(other.get("a"), None);
+        assert!(other.is_empty());
+    }
+}